@@ -28,7 +28,22 @@ pub enum Opcode {
     OP_SET_GLOBAL = 18,
     OP_GET_LOCAL = 19,
     OP_SET_LOCAL = 20,
-
+    OP_GET_PROPERTY = 21,
+    OP_INVOKE = 22,
+    OP_CHECK_TYPE = 23,
+    OP_BUILD_LIST = 24,
+    OP_GET_INDEX = 25,
+    OP_SLICE = 26,
+    OP_SET_SLICE = 27,
+    OP_LIST_APPEND = 28,
+    OP_LIST_EXTEND = 29,
+    OP_INVOKE_LIST = 30, // like OP_INVOKE, with the arguments packed in a list
+    OP_SWAP = 31,
+    OP_CALL = 32,
+    OP_CALL_LIST = 33, // like OP_CALL, with the arguments packed in a list
+    OP_IS = 34,
+    OP_WITH = 35, // copy of a record with the given fields replaced
+    OP_FREEZE = 36,
 }
 
 impl TryFrom<u8> for Opcode {
@@ -56,6 +71,22 @@ impl TryFrom<u8> for Opcode {
             val if val == Opcode::OP_SET_GLOBAL as u8 => Ok(Opcode::OP_SET_GLOBAL),
             val if val == Opcode::OP_GET_LOCAL as u8 => Ok(Opcode::OP_GET_LOCAL),
             val if val == Opcode::OP_SET_LOCAL as u8 => Ok(Opcode::OP_SET_LOCAL),
+            val if val == Opcode::OP_GET_PROPERTY as u8 => Ok(Opcode::OP_GET_PROPERTY),
+            val if val == Opcode::OP_INVOKE as u8 => Ok(Opcode::OP_INVOKE),
+            val if val == Opcode::OP_CHECK_TYPE as u8 => Ok(Opcode::OP_CHECK_TYPE),
+            val if val == Opcode::OP_BUILD_LIST as u8 => Ok(Opcode::OP_BUILD_LIST),
+            val if val == Opcode::OP_GET_INDEX as u8 => Ok(Opcode::OP_GET_INDEX),
+            val if val == Opcode::OP_SLICE as u8 => Ok(Opcode::OP_SLICE),
+            val if val == Opcode::OP_SET_SLICE as u8 => Ok(Opcode::OP_SET_SLICE),
+            val if val == Opcode::OP_LIST_APPEND as u8 => Ok(Opcode::OP_LIST_APPEND),
+            val if val == Opcode::OP_LIST_EXTEND as u8 => Ok(Opcode::OP_LIST_EXTEND),
+            val if val == Opcode::OP_INVOKE_LIST as u8 => Ok(Opcode::OP_INVOKE_LIST),
+            val if val == Opcode::OP_SWAP as u8 => Ok(Opcode::OP_SWAP),
+            val if val == Opcode::OP_CALL as u8 => Ok(Opcode::OP_CALL),
+            val if val == Opcode::OP_CALL_LIST as u8 => Ok(Opcode::OP_CALL_LIST),
+            val if val == Opcode::OP_IS as u8 => Ok(Opcode::OP_IS),
+            val if val == Opcode::OP_WITH as u8 => Ok(Opcode::OP_WITH),
+            val if val == Opcode::OP_FREEZE as u8 => Ok(Opcode::OP_FREEZE),
             _ => Err(())
         }
     }
@@ -88,6 +119,14 @@ impl Chunk {
         self.constants.get_values().len() - 1
     }
 
+    // drop the code from offset on and the constants from constant on, so the
+    // compiler can replace what it just emitted
+    pub fn truncate(&mut self, offset: usize, constant: usize) {
+        self.code.truncate(offset);
+        self.lines.truncate(offset);
+        self.constants.truncate(constant);
+    }
+
     pub fn get_code(&self) -> &Vec<u8> {
         &self.code
     }
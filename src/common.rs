@@ -0,0 +1,2 @@
+// Dump the compiled chunk before it is executed
+pub const DEBUG_PRINT_CODE: bool = false;
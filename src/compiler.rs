@@ -1,23 +1,28 @@
 
-use crate::object::ObjString;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use crate::object::{ObjString, ObjEnum, ObjRecord, ObjList};
 use crate::scanner::{Token, TokenType};
 use crate::chunk::{Chunk, Opcode};
 use crate::value::{Value, ValueType, AsValue};
 use super::scanner::Scanner;
 use crate::parser::{Precedence, get_rule};
+use crate::types::{StaticType, TypeMode};
 
-const UINT8_COUNT: usize = usize::MAX ;
+const UINT8_COUNT: usize = u8::MAX as usize + 1;
 
 pub struct Local<'a> {
     name: Token<'a>,
-    depth: i32
+    depth: i32,
+    type_: StaticType
 }
 
 impl<'a> Local<'a> {
     fn new() -> Self {
         Local{
             name: Token::init_token(""),
-            depth: Default::default()
+            depth: Default::default(),
+            type_: StaticType::TYPE_ANY
         }
     }
 }
@@ -39,12 +44,62 @@ impl<'a> Compiler<'a> {
     }
 }
 
+// Names the compiler knows more about than their value, like enums, with
+// the depth of the scope that declared them. the latest entry for a name
+// wins, a None entry is a variable hiding an outer declaration
+struct Declarations<T> {
+    entries: Vec<(String, u32, Option<T>)>
+}
+
+impl<T> Declarations<T> {
+    fn new() -> Self {
+        Declarations { entries: Vec::new() }
+    }
+
+    fn get(&self, name: &str) -> Option<&T> {
+        match self.entries.iter().rev().find(|(entry, _, _)| entry == name) {
+            Some((_, _, value)) => value.as_ref(),
+            None => None
+        }
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    fn declare(&mut self, name: String, depth: u32, value: T) {
+        self.entries.push((name, depth, Some(value)));
+    }
+
+    fn hide(&mut self, name: &str, depth: u32) {
+        if self.contains(name) {
+            self.entries.push((String::from(name), depth, None));
+        }
+    }
+
+    fn end_scope(&mut self, depth: u32) {
+        self.entries.retain(|(_, entry_depth, _)| *entry_depth <= depth);
+    }
+}
+
 pub struct CompilerParser<'a> {
     current_compiler: Compiler<'a>,
     current: Token<'a>,
     pub prev: Token<'a>,
     scanner: &'a mut Scanner<'a>,
     chunk:  &'a mut Chunk,
+    enums: Declarations<ObjEnum>,
+    records: Declarations<ObjRecord>,
+    enum_ref: Option<(usize, ObjEnum)>,
+    variable_ref: Option<(usize, u8, u8)>,
+    global_types: HashMap<String, StaticType>,
+    last_type: StaticType,
+    type_mode: TypeMode,
+    bigint: bool,
+    pending_doc: Vec<String>,
+    declaration_doc: Option<String>,
+    docs: Vec<(String, String)>,
+    errors: Option<Vec<String>>,
     had_error: bool,
     panic_mode: bool
 }
@@ -55,6 +110,41 @@ impl<'a> CompilerParser<'a>  {
         &self.prev
     }
 
+    pub fn set_type_mode(&mut self, type_mode: TypeMode) {
+        self.type_mode = type_mode
+    }
+
+    pub fn set_bigint(&mut self, bigint: bool) {
+        self.bigint = bigint
+    }
+
+    pub fn get_bigint(&self) -> bool {
+        self.bigint
+    }
+
+    pub fn set_last_type(&mut self, type_: StaticType) {
+        self.last_type = type_
+    }
+
+    pub fn take_last_type(&mut self) -> StaticType {
+        std::mem::replace(&mut self.last_type, StaticType::TYPE_ANY)
+    }
+
+    // doc comments of the compiled declarations, keyed by declaration name
+    pub fn get_docs(&self) -> &Vec<(String, String)> {
+        &self.docs
+    }
+
+    // doc comment lines seen since the last declaration
+    fn take_doc(&mut self) -> Option<String> {
+        if self.pending_doc.is_empty() {
+            return None;
+        }
+        let doc = self.pending_doc.join("\n");
+        self.pending_doc.clear();
+        Some(doc)
+    }
+
     pub fn init_compiler(scanner: &'a mut Scanner<'a>, chunk: &'a mut Chunk) -> Self {
         CompilerParser { 
             current_compiler: Compiler::new(),
@@ -62,6 +152,18 @@ impl<'a> CompilerParser<'a>  {
             prev: Token::init_token(""),
             chunk,
             scanner,
+            enums: Declarations::new(),
+            records: Declarations::new(),
+            enum_ref: None,
+            variable_ref: None,
+            global_types: HashMap::new(),
+            last_type: StaticType::TYPE_ANY,
+            type_mode: TypeMode::TYPE_IGNORE,
+            bigint: true,
+            pending_doc: Vec::new(),
+            declaration_doc: None,
+            docs: Vec::new(),
+            errors: None,
             had_error: false,
             panic_mode: false
      }
@@ -76,13 +178,36 @@ impl<'a> CompilerParser<'a>  {
         return !self.had_error;
     }
 
+    // a single expression whose value is left on the stack
+    pub fn compile_expression(&mut self) -> bool {
+        self.advance();
+        self.expression();
+        self.consume(&TokenType::EOF, "Expect end of expression.");
+        self.end_compiler();
+        return !self.had_error;
+    }
+
+    // keep error messages for take_errors instead of printing them
+    pub fn collect_errors(&mut self) {
+        self.errors = Some(Vec::new());
+    }
+
+    pub fn take_errors(&mut self) -> Vec<String> {
+        self.errors.take().unwrap_or_default()
+    }
+
     pub fn expression(&mut self) {
         self.parse_precedence(&Precedence::PREC_ASSIGNMENT);
     }
 
     pub fn declaration(&mut self) {
-        if *self.current.get_type() == TokenType::VAR {
+        self.declaration_doc = self.take_doc();
+        if self.match_(TokenType::VAR) {
             self.var_declaration();
+        } else if self.match_(TokenType::ENUM) {
+            self.enum_declaration();
+        } else if self.match_(TokenType::RECORD) {
+            self.record_declaration();
         } else {
             self.statement();
         }
@@ -93,17 +218,290 @@ impl<'a> CompilerParser<'a>  {
 
     pub fn var_declaration(&mut self) {
         let global: u8 = self.parse_variable("Expect variable name");
+        let name = self.prev;
+        let declared = if self.match_(TokenType::COLON) {
+            self.type_annotation()
+        } else {
+            StaticType::TYPE_ANY
+        };
         if self.match_(TokenType::EQUAL) {
             self.expression();
+            let value_type = self.take_last_type();
+            self.check_assignment(&name, &declared, &value_type);
         } else {
             self.emit_byte(Opcode::OP_NIL as u8);
         }
         self.consume(&TokenType::SEMICOLON, "Expect ';' at the end of expression.");
+        if self.current_compiler.scope_depth > 0 {
+            self.current_compiler.locals[(self.current_compiler.local_count - 1) as usize].type_ = declared;
+        } else {
+            self.global_types.insert(String::from(name.get_sized_content()), declared);
+        }
+        self.define_variable(global);
+    }
+
+    fn type_annotation(&mut self) -> StaticType {
+        self.consume(&TokenType::IDENTIFIER, "Expect type name after ':'");
+        let name = self.prev.get_sized_content();
+        if let Some(type_) = StaticType::from_name(name) {
+            return type_;
+        }
+        if self.enums.contains(name) {
+            return StaticType::TYPE_ENUM(String::from(name));
+        }
+        if self.records.contains(name) {
+            return StaticType::TYPE_RECORD(String::from(name));
+        }
+        let message = format!("Unknown type {}", name);
+        self.type_error(&message);
+        StaticType::TYPE_ANY
+    }
+
+    // names accepted on the right of 'is', on top of the annotation types
+    pub fn is_type_name(&self, name: &str) -> bool {
+        StaticType::from_name(name).is_some() || self.enums.contains(name)
+            || self.records.contains(name)
+            || name == "Enum" || name == "Function" || name == "Record"
+    }
+
+    // declared type of a variable, locals are looked up by their slot
+    pub fn variable_type(&self, name: &Token, local: i32) -> StaticType {
+        if local != -1 {
+            return self.current_compiler.locals[local as usize].type_.clone();
+        }
+        match self.global_types.get(name.get_sized_content()) {
+            Some(type_) => type_.clone(),
+            None => StaticType::TYPE_ANY
+        }
+    }
+
+    // check a value stored into an annotated variable, in strict mode the
+    // check is repeated at runtime against the value on top of the stack
+    pub fn check_assignment(&mut self, name: &Token, declared: &StaticType, value_type: &StaticType) {
+        if !declared.accepts(value_type) {
+            let message = format!("Cannot assign {} to {} of type {}",
+                value_type.get_name(), name.get_sized_content(), declared.get_name());
+            self.type_error(&message);
+        }
+        if self.type_mode == TypeMode::TYPE_STRICT && *declared != StaticType::TYPE_ANY {
+            let type_name = ObjString::from(String::from(declared.get_name()));
+            let constant = self.make_constant(obj_val!(Box::from(type_name)));
+            self.emit_bytes(Opcode::OP_CHECK_TYPE as u8, constant);
+        }
+    }
+
+    pub fn check_unary(&mut self, op: &TokenType, operand: &StaticType) {
+        match StaticType::unary_result(op, operand) {
+            Ok(type_) => self.last_type = type_,
+            Err(message) => self.type_error(message)
+        }
+    }
+
+    pub fn check_binary(&mut self, op: &TokenType, left: &StaticType, right: &StaticType) {
+        match StaticType::binary_result(op, left, right) {
+            Ok(type_) => self.last_type = type_,
+            Err(message) => self.type_error(message)
+        }
+    }
+
+    pub fn enum_declaration(&mut self) {
+        let global: u8 = self.parse_variable("Expect enum name");
+        let name = String::from(self.prev.get_sized_content());
+        let mut enum_obj = ObjEnum::new(name.clone());
+        self.consume(&TokenType::LEFT_BRACE, "Expect '{' before enum body.");
+        while !self.check(TokenType::RIGHT_BRACE) && !self.check(TokenType::EOF) {
+            let doc = self.take_doc();
+            self.consume(&TokenType::IDENTIFIER, "Expect variant name");
+            let variant = String::from(self.prev.get_sized_content());
+            if let Some(doc) = doc {
+                self.docs.push((format!("{}.{}", name, variant), doc));
+            }
+            if enum_obj.get_variant(&variant).is_some() {
+                self.error("A variant with the same name already exist");
+            }
+            let mut fields = Vec::new();
+            if self.match_(TokenType::LEFT_PAREN) {
+                if !self.check(TokenType::RIGHT_PAREN) {
+                    loop {
+                        self.consume(&TokenType::IDENTIFIER, "Expect associated value name");
+                        fields.push(String::from(self.prev.get_sized_content()));
+                        if !self.match_(TokenType::COMMA) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(&TokenType::RIGHT_PAREN, "Expect ')' after associated values.");
+            }
+            enum_obj.add_variant(variant, fields);
+            if !self.match_(TokenType::COMMA) {
+                break;
+            }
+        }
+        self.consume(&TokenType::RIGHT_BRACE, "Expect '}' after enum body.");
+        self.emit_constant(obj_val!(Box::from(enum_obj.clone())));
+        self.define_variable(global);
+        let depth = self.current_compiler.scope_depth;
+        self.enums.declare(name, depth, enum_obj);
+    }
+
+    pub fn record_declaration(&mut self) {
+        let global: u8 = self.parse_variable("Expect record name");
+        let name = String::from(self.prev.get_sized_content());
+        let mut fields: Vec<String> = Vec::new();
+        self.consume(&TokenType::LEFT_PAREN, "Expect '(' after record name.");
+        if !self.check(TokenType::RIGHT_PAREN) {
+            loop {
+                self.consume(&TokenType::IDENTIFIER, "Expect field name");
+                let field = String::from(self.prev.get_sized_content());
+                if fields.contains(&field) {
+                    self.error("A field with the same name already exist");
+                }
+                fields.push(field);
+                if !self.match_(TokenType::COMMA) {
+                    break;
+                }
+            }
+        }
+        self.consume(&TokenType::RIGHT_PAREN, "Expect ')' after record fields.");
+        self.consume(&TokenType::SEMICOLON, "Expect ';' after record declaration.");
+        let record = ObjRecord::new(name.clone(), fields);
+        self.emit_constant(obj_val!(Box::from(record.clone())));
         self.define_variable(global);
+        let depth = self.current_compiler.scope_depth;
+        self.records.declare(name, depth, record);
+    }
+
+    // remember that the last emitted instruction loaded a variable, so that a
+    // slice assignment or freeze on it can store the updated value back
+    pub fn mark_variable_ref(&mut self, set_op: u8, arg: u8) {
+        self.variable_ref = Some((self.chunk.get_code().len(), set_op, arg));
+    }
+
+    pub fn resolved_variable(&mut self) -> Option<(u8, u8)> {
+        match self.variable_ref.take() {
+            Some((end, set_op, arg)) if end == self.chunk.get_code().len() => Some((set_op, arg)),
+            _ => None
+        }
+    }
+
+    // where the next instruction and constant will go, see fold_frozen_list
+    pub fn chunk_mark(&self) -> (usize, usize) {
+        (self.chunk.get_code().len(), self.chunk.get_constants().get_values().len())
+    }
+
+    // when the code emitted since mark only builds a list out of constants,
+    // replace it with one frozen list constant. false if it does anything else
+    pub fn fold_frozen_list(&mut self, mark: (usize, usize)) -> bool {
+        let (offset, constant) = mark;
+        let code = &self.chunk.get_code()[offset..];
+        let constants = self.chunk.get_constants().get_values();
+        let mut values = Vec::new();
+        let mut index = 0;
+        while index < code.len() {
+            match code[index].try_into() {
+                Ok(Opcode::OP_CONSTANT) if index + 2 < code.len() => {
+                    values.push(constants[code[index + 1] as usize].clone());
+                    index += 2;
+                },
+                Ok(Opcode::OP_NIL) => { values.push(nill!()); index += 1; },
+                Ok(Opcode::OP_TRUE) => { values.push(boolean_val!(true)); index += 1; },
+                Ok(Opcode::OP_FALSE) => { values.push(boolean_val!(false)); index += 1; },
+                Ok(Opcode::OP_BUILD_LIST) if index + 2 == code.len() && code[index + 1] as usize == values.len() => break,
+                _ => return false
+            }
+        }
+        if index == code.len() {
+            return false;
+        }
+        let mut list = ObjList::from(values);
+        list.freeze();
+        self.chunk.truncate(offset, constant);
+        self.emit_constant(obj_val!(Box::from(list)));
+        true
+    }
+
+    // remember that the last emitted instruction loaded a known enum, so that
+    // variant access on it can be checked at compile time
+    pub fn mark_enum_ref(&mut self, name: &Token) {
+        self.enum_ref = match self.enums.get(name.get_sized_content()) {
+            Some(enum_obj) => Some((self.chunk.get_code().len(), enum_obj.clone())),
+            None => None
+        };
+    }
+
+    pub fn resolved_enum(&mut self) -> Option<ObjEnum> {
+        match self.enum_ref.take() {
+            Some((end, enum_obj)) if end == self.chunk.get_code().len() => Some(enum_obj),
+            _ => None
+        }
+    }
+
+    pub fn check_enum_variant(&mut self, receiver: &Option<ObjEnum>, name: &Token, arg_count: Option<u8>) {
+        let enum_obj = match receiver {
+            Some(enum_obj) => enum_obj,
+            None => return
+        };
+        let message = match enum_obj.get_variant(name.get_sized_content()) {
+            None => format!("Enum {} has no variant {}", enum_obj.get_name(), name.get_sized_content()),
+            Some(variant) => match arg_count {
+                None if variant.get_arity() > 0 => format!("Variant {}.{} expects {} values",
+                    enum_obj.get_name(), variant.get_name(), variant.get_arity()),
+                Some(count) if count as usize != variant.get_arity() => format!("Variant {}.{} expects {} values but got {}",
+                    enum_obj.get_name(), variant.get_name(), variant.get_arity(), count),
+                _ => return
+            }
+        };
+        self.error(&message);
+    }
+
+    // argument count, or None when a spread argument packed them into a list.
+    // count arguments were already pushed by the caller
+    pub fn argument_list(&mut self, count: u8) -> Option<u8> {
+        let count = self.element_list(TokenType::RIGHT_PAREN, count, "Can't have more than 255 arguments.");
+        self.consume(&TokenType::RIGHT_PAREN, "Expect ')' after arguments.");
+        count
+    }
+
+    // comma separated expressions up to closing, each of them possibly
+    // spread with '...'. the elements are left on the stack and counted
+    // until the first spread, from there on they are collected in a list
+    pub fn element_list(&mut self, closing: TokenType, count: u8, too_many: &str) -> Option<u8> {
+        let mut count = count;
+        let mut spread = false;
+        if !self.check(closing) {
+            loop {
+                if self.match_(TokenType::ELLIPSIS) {
+                    if !spread {
+                        self.emit_bytes(Opcode::OP_BUILD_LIST as u8, count);
+                        spread = true;
+                    }
+                    self.expression();
+                    self.emit_byte(Opcode::OP_LIST_EXTEND as u8);
+                } else {
+                    self.expression();
+                    if spread {
+                        self.emit_byte(Opcode::OP_LIST_APPEND as u8);
+                    } else if count == u8::MAX {
+                        self.error(too_many);
+                    } else {
+                        count += 1;
+                    }
+                }
+                if !self.match_(TokenType::COMMA) {
+                    break;
+                }
+            }
+        }
+        if spread { None } else { Some(count) }
     }
 
     pub fn parse_variable(&mut self, error_message: &str) -> u8 {
         self.consume(&TokenType::IDENTIFIER, error_message);
+        self.enums.hide(self.prev.get_sized_content(), self.current_compiler.scope_depth);
+        self.records.hide(self.prev.get_sized_content(), self.current_compiler.scope_depth);
+        if let Some(doc) = self.declaration_doc.take() {
+            self.docs.push((String::from(self.prev.get_sized_content()), doc));
+        }
         self.declare_variable();
         if self.current_compiler.scope_depth > 0 {
             return 0;
@@ -182,18 +580,21 @@ impl<'a> CompilerParser<'a>  {
     pub fn sync(&mut self) {
         self.panic_mode = false;
         while *self.current.get_type() != TokenType::EOF {
-            if *self.current.get_type() == TokenType::SEMICOLON {return}
+            if *self.prev.get_type() == TokenType::SEMICOLON {return}
             match *self.current.get_type() {
-                TokenType::CLASS => (),
-                TokenType::FUN => (),
-                TokenType::VAR => (),
-                TokenType::FOR => (),
-                TokenType::IF => (),
-                TokenType::WHILE => (),
-                TokenType::PRINT => (),
-                TokenType::RETURN => (),
-                _ => break
+                TokenType::CLASS => return,
+                TokenType::FUN => return,
+                TokenType::VAR => return,
+                TokenType::ENUM => return,
+                TokenType::RECORD => return,
+                TokenType::FOR => return,
+                TokenType::IF => return,
+                TokenType::WHILE => return,
+                TokenType::PRINT => return,
+                TokenType::RETURN => return,
+                _ => ()
             }
+            self.advance();
         }
     }
 
@@ -216,19 +617,29 @@ impl<'a> CompilerParser<'a>  {
 
     fn end_scope(&mut self) {
         self.current_compiler.scope_depth -= 1;
+        self.enums.end_scope(self.current_compiler.scope_depth);
+        self.records.end_scope(self.current_compiler.scope_depth);
         let mut count = self.current_compiler.local_count;
         while count > 0 && 
         (&self.current_compiler.locals[(count - 1) as usize]).depth > self.current_compiler.scope_depth as i32 {
             self.emit_byte(Opcode::OP_POP as u8);
+            self.current_compiler.locals.pop();
             self.current_compiler.local_count -= 1;
             count = self.current_compiler.local_count
         }
     }
 
-    fn check(& self, token_kind: TokenType) -> bool {
+    pub fn check(& self, token_kind: TokenType) -> bool {
         return *self.current.get_type() == token_kind;
     }
 
+    // peek at the token after the current one, scanning a copy of the scanner
+    // so nothing is consumed
+    pub fn check_next(& self, token_kind: TokenType) -> bool {
+        let mut scanner = *self.scanner;
+        return *scanner.scan_token().get_type() == token_kind;
+    }
+
     fn block(&mut self) -> () {
         while !self.check(TokenType::RIGHT_BRACE) && !self.check(TokenType::EOF) {
             self.declaration();
@@ -319,6 +730,11 @@ impl<'a> CompilerParser<'a>  {
         //let mut line = 0;
         loop {
             self.current = self.scanner.scan_token();
+            if *self.current.get_type() == TokenType::DOC_COMMENT {
+                let line = &self.current.get_sized_content()[3..];
+                self.pending_doc.push(String::from(line.strip_prefix(' ').unwrap_or(line)));
+                continue;
+            }
             if *self.current.get_type() != TokenType::ERROR {
                 break;
             }
@@ -330,30 +746,50 @@ impl<'a> CompilerParser<'a>  {
 
     //parser error handling 
     fn error_at_current(&mut self, message: &str) {
-        self.error_at(&self.current, message);
+        let token = self.current;
+        self.error_at(&token, message);
         self.had_error = true;
         self.panic_mode = true;
     }
 
-    fn error(&mut self, message: &str) {
-        self.error_at(&self.prev, message);
+    // type errors are only reported when annotations are being checked
+    fn type_error(&mut self, message: &str) {
+        if self.type_mode == TypeMode::TYPE_IGNORE {
+            return;
+        }
+        let message = format!("[line {}, column {} ] Type error {}", self.prev.get_line(), self.prev.get_column(), message);
+        self.report(message);
+        self.had_error = true;
+    }
+
+    pub fn error(&mut self, message: &str) {
+        let token = self.prev;
+        self.error_at(&token, message);
         self.had_error = true;
         self.panic_mode = true;
     }
 
-    fn error_at(&self, token: &Token, message: &str) {
+    fn error_at(&mut self, token: &Token, message: &str) {
         if self.panic_mode {
             ()
         }
-        print!("\n[line {} ] Error ", token.get_line());
+        let mut res = format!("[line {}, column {} ] Error ", token.get_line(), token.get_column());
         if *token.get_type() == TokenType::EOF {
-            print!(" at end")
+            res.push_str(" at end")
         } else if *token.get_type() == TokenType::ERROR {
     
         } else {
-            print!(" at {} ", token.get_start())
+            res.push_str(&format!(" at '{}' ", token.get_sized_content()))
+        }
+        res.push_str(message);
+        self.report(res);
+    }
+
+    fn report(&mut self, message: String) {
+        match self.errors.as_mut() {
+            Some(errors) => errors.push(message),
+            None => println!("\n{}", message)
         }
-        println!("{}", message);
     }
 }
 
@@ -42,6 +42,22 @@ fn disassemble_instruction(chunk: &Chunk, offset: usize) -> usize {
         Ok(Opcode::OP_SET_GLOBAL) => constant_instruction("OP_SET_GLOBAL", chunk, offset),
         Ok(Opcode::OP_GET_LOCAL) => byte_instruction("OP_GET_LOCAL", chunk, offset),
         Ok(Opcode::OP_SET_LOCAL) => byte_instruction("OP_SET_LOCAL", chunk, offset),
+        Ok(Opcode::OP_GET_PROPERTY) => constant_instruction("OP_GET_PROPERTY", chunk, offset),
+        Ok(Opcode::OP_INVOKE) => invoke_instruction("OP_INVOKE", chunk, offset),
+        Ok(Opcode::OP_CHECK_TYPE) => constant_instruction("OP_CHECK_TYPE", chunk, offset),
+        Ok(Opcode::OP_BUILD_LIST) => byte_instruction("OP_BUILD_LIST", chunk, offset),
+        Ok(Opcode::OP_GET_INDEX) => simple_instruction("OP_GET_INDEX", offset),
+        Ok(Opcode::OP_SLICE) => simple_instruction("OP_SLICE", offset),
+        Ok(Opcode::OP_SET_SLICE) => simple_instruction("OP_SET_SLICE", offset),
+        Ok(Opcode::OP_LIST_APPEND) => simple_instruction("OP_LIST_APPEND", offset),
+        Ok(Opcode::OP_LIST_EXTEND) => simple_instruction("OP_LIST_EXTEND", offset),
+        Ok(Opcode::OP_INVOKE_LIST) => constant_instruction("OP_INVOKE_LIST", chunk, offset),
+        Ok(Opcode::OP_SWAP) => simple_instruction("OP_SWAP", offset),
+        Ok(Opcode::OP_CALL) => byte_instruction("OP_CALL", chunk, offset),
+        Ok(Opcode::OP_CALL_LIST) => simple_instruction("OP_CALL_LIST", offset),
+        Ok(Opcode::OP_IS) => constant_instruction("OP_IS", chunk, offset),
+        Ok(Opcode::OP_WITH) => byte_instruction("OP_WITH", chunk, offset),
+        Ok(Opcode::OP_FREEZE) => simple_instruction("OP_FREEZE", offset),
         _ => offset + 1
     }
 }
@@ -64,3 +80,12 @@ fn byte_instruction(name: &str, chunk: &Chunk, offset: usize) -> usize {
     println!("{} => index {}", name, *constant_index);
     offset + 2
 }
+
+fn invoke_instruction(name: &str, chunk: &Chunk, offset: usize) -> usize {
+    let constant_index = chunk.get_code().get(offset + 1).unwrap();
+    let arg_count = chunk.get_code().get(offset + 2).unwrap();
+    println!("{} => ({} args) index {}", name, *arg_count, *constant_index);
+    let constant = chunk.get_constants().get_values().get(*constant_index as usize).unwrap();
+    ValueArray::print_value(constant);
+    offset + 3
+}
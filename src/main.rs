@@ -7,18 +7,40 @@ mod vm;
 mod scanner;
 mod parser;
 mod object;
+mod types;
+mod native;
 
 use chunk::Chunk;
+use compiler::CompilerParser;
+use scanner::Scanner;
+use types::TypeMode;
 use vm::InterpretResult;
 use vm::VirtualMachine;
 use std::{env, io, fs};
 
+#[derive(Clone, Copy)]
+struct Options {
+    type_mode: TypeMode,
+    bigint: bool
+}
+
 fn main() -> () {
-    let x: Vec<_> = env::args().collect();
+    let mut x: Vec<_> = env::args().collect();
+    let mut options = Options { type_mode: TypeMode::TYPE_IGNORE, bigint: true };
+    if take_flag(&mut x, "--strict") {
+        options.type_mode = TypeMode::TYPE_STRICT;
+    }
+    if take_flag(&mut x, "--no-bigint") {
+        options.bigint = false;
+    }
     if x.len() == 2 {
-        run_file(&x[1])
+        run_file(&x[1], options)
+    }else if x.len() == 3 && x[1] == "check" {
+        check_file(&x[2])
+    }else if x.len() == 3 && x[1] == "doc" {
+        doc_file(&x[2])
     }else if x.len() < 2 {
-        repl();
+        repl(options);
     }else {
         panic!("Error {}", -5);
     }
@@ -43,18 +65,25 @@ fn main() -> () {
     // vm.interpret(&chunk);
 }
 
-fn repl() {
+// remove a flag from the arguments, returning whether it was given
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != len
+}
+
+fn repl(options: Options) {
     let mut content = String::new();
     loop {
         println!("\nWelcome to CR REPL. Type in the source >>");
         io::stdin().read_line(&mut content).unwrap();
-        run(content.trim());
+        run(content.trim(), options);
     }
 }
 
-fn run_file(path: &str) {
+fn run_file(path: &str, options: Options) {
     let content = fs::read_to_string(path).expect("path does not exist");
-    let interpret_result = run(content.trim());
+    let interpret_result = run(content.trim(), options);
     match interpret_result {
         InterpretResult::INTERPRET_COMPILE_ERROR => panic!("Error 65 has occured"),
         InterpretResult::INTERPRET_RUNTIME_ERROR => panic!("Error 66 has occured"),
@@ -62,10 +91,38 @@ fn run_file(path: &str) {
     }
 }
 
-fn run(source: &str) -> InterpretResult {
+fn run(source: &str, options: Options) -> InterpretResult {
     let mut vm = VirtualMachine::init_virtual_machine();
     let mut chunk = Chunk::init_chunk();
-    vm.interpret(source, &mut chunk);
+    vm.set_type_mode(options.type_mode);
+    vm.set_bigint(options.bigint);
+    vm.interpret(source, &mut chunk)
     //disassemble_chunk(&chunk, "test chunk");
-    InterpretResult::INTERPRET_OK
+}
+
+// compile the file with type checking only, no bytecode is run
+fn check_file(path: &str) {
+    let content = fs::read_to_string(path).expect("path does not exist");
+    let mut chunk = Chunk::init_chunk();
+    let mut sc = Scanner::init_scanner(content.trim());
+    let mut compiler = CompilerParser::init_compiler(&mut sc, &mut chunk);
+    compiler.set_type_mode(TypeMode::TYPE_CHECK);
+    if !compiler.compile() {
+        panic!("Error 65 has occured")
+    }
+}
+
+// print the doc comments attached to the declarations of the file
+fn doc_file(path: &str) {
+    let content = fs::read_to_string(path).expect("path does not exist");
+    let mut chunk = Chunk::init_chunk();
+    let mut sc = Scanner::init_scanner(content.trim());
+    let mut compiler = CompilerParser::init_compiler(&mut sc, &mut chunk);
+    if !compiler.compile() {
+        panic!("Error 65 has occured")
+    }
+    for (name, doc) in compiler.get_docs() {
+        println!("== {} ==", name);
+        println!("{}\n", doc);
+    }
 }
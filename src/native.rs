@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use crate::object::{Obj, ObjType, ObjString, ObjList};
+use crate::value::{Value, ValueType, AsValue, is_obj_type, type_name};
+use crate::vm::VirtualMachine;
+
+// A method implemented in rust, called with its receiver and arguments
+pub type NativeMethod = fn(&Value, &[Value]) -> Result<Value, String>;
+
+// A global function implemented in rust, called with the vm and its arguments
+pub type NativeFn = fn(&mut VirtualMachine, &[Value]) -> Result<Value, String>;
+
+// Functions defined as globals when the vm starts
+pub fn native_functions() -> Vec<(&'static str, NativeFn)> {
+    vec![
+        ("type", native_type),
+        ("eval", native_eval),
+        ("isFrozen", native_is_frozen)
+    ]
+}
+
+// Native method tables, keyed on the type of the receiver
+pub fn native_methods() -> HashMap<ObjType, HashMap<String, NativeMethod>> {
+    let mut methods = HashMap::new();
+    methods.insert(ObjType::OBJ_STRING, string_methods());
+    methods.insert(ObjType::OBJ_LIST, list_methods());
+    methods
+}
+
+fn string_methods() -> HashMap<String, NativeMethod> {
+    let mut methods: HashMap<String, NativeMethod> = HashMap::new();
+    methods.insert(String::from("len"), string_len);
+    methods.insert(String::from("slice"), string_slice);
+    methods.insert(String::from("indexOf"), string_index_of);
+    methods.insert(String::from("split"), string_split);
+    methods.insert(String::from("replace"), string_replace);
+    methods.insert(String::from("upper"), string_upper);
+    methods.insert(String::from("lower"), string_lower);
+    methods.insert(String::from("trim"), string_trim);
+    methods.insert(String::from("startsWith"), string_starts_with);
+    methods.insert(String::from("chars"), string_chars);
+    methods
+}
+
+fn list_methods() -> HashMap<String, NativeMethod> {
+    let mut methods: HashMap<String, NativeMethod> = HashMap::new();
+    methods.insert(String::from("len"), list_len);
+    methods
+}
+
+fn native_type(_vm: &mut VirtualMachine, args: &[Value]) -> Result<Value, String> {
+    check_arity("type", args, 1, 1)?;
+    Ok(string_val(type_name(&args[0])))
+}
+
+fn native_eval(vm: &mut VirtualMachine, args: &[Value]) -> Result<Value, String> {
+    check_arity("eval", args, 1, 1)?;
+    let source = string_arg("eval", args, 0)?;
+    vm.eval(&source)
+}
+
+fn native_is_frozen(_vm: &mut VirtualMachine, args: &[Value]) -> Result<Value, String> {
+    check_arity("isFrozen", args, 1, 1)?;
+    Ok(boolean_val!(!is_list!(args[0]) || as_list!(args[0]).is_frozen()))
+}
+
+fn string_val(string: String) -> Value {
+    obj_val!(Box::from(ObjString::from(string)))
+}
+
+fn list_val(values: Vec<Value>) -> Value {
+    obj_val!(Box::from(ObjList::from(values)))
+}
+
+fn check_arity(name: &str, args: &[Value], min: usize, max: usize) -> Result<(), String> {
+    if args.len() < min || args.len() > max {
+        if min == max {
+            return Err(format!("{} expects {} arguments but got {}", name, min, args.len()));
+        }
+        return Err(format!("{} expects {} to {} arguments but got {}", name, min, max, args.len()));
+    }
+    Ok(())
+}
+
+fn string_arg(name: &str, args: &[Value], index: usize) -> Result<String, String> {
+    if !is_str!(args[index]) {
+        return Err(format!("{} expects a string as argument {}", name, index + 1));
+    }
+    Ok(String::from(as_str_raw!(args[index])))
+}
+
+fn integer_arg(name: &str, args: &[Value], index: usize) -> Result<i64, String> {
+    if !is_number!(args[index]) || as_number!(args[index]).fract() != 0.0 {
+        return Err(format!("{} expects an integer as argument {}", name, index + 1));
+    }
+    Ok(as_number!(args[index]) as i64)
+}
+
+// clamp a character index into 0..=len, negative indices count from the end
+pub fn resolve_index(index: i64, len: usize) -> usize {
+    let len = len as i64;
+    let index = if index < 0 { index + len } else { index };
+    index.max(0).min(len) as usize
+}
+
+fn string_len(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("len", args, 0, 0)?;
+    Ok(number_val!(as_str_raw!(*receiver).chars().count() as f64))
+}
+
+fn string_slice(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("slice", args, 1, 2)?;
+    let string = as_str!(*receiver);
+    let len = string.get_string().chars().count();
+    let start = resolve_index(integer_arg("slice", args, 0)?, len);
+    let end = if args.len() == 2 { resolve_index(integer_arg("slice", args, 1)?, len) } else { len };
+    let res: String = string.get_string().chars().skip(start).take(end.saturating_sub(start)).collect();
+    Ok(string_val(res))
+}
+
+fn string_index_of(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("indexOf", args, 1, 1)?;
+    let string = as_str!(*receiver);
+    let needle = string_arg("indexOf", args, 0)?;
+    match string.get_string().find(&needle) {
+        Some(offset) => Ok(number_val!(string.get_string()[..offset].chars().count() as f64)),
+        None => Ok(number_val!(-1.0))
+    }
+}
+
+fn string_split(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("split", args, 1, 1)?;
+    let separator = string_arg("split", args, 0)?;
+    if separator.is_empty() {
+        return string_chars(receiver, &[]);
+    }
+    let parts = as_str_raw!(*receiver).split(separator.as_str())
+        .map(|part| string_val(String::from(part))).collect();
+    Ok(list_val(parts))
+}
+
+fn string_replace(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("replace", args, 2, 2)?;
+    let from = string_arg("replace", args, 0)?;
+    let to = string_arg("replace", args, 1)?;
+    if from.is_empty() {
+        return Ok(receiver.clone());
+    }
+    Ok(string_val(as_str_raw!(*receiver).replace(from.as_str(), to.as_str())))
+}
+
+fn string_upper(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("upper", args, 0, 0)?;
+    Ok(string_val(as_str_raw!(*receiver).to_uppercase()))
+}
+
+fn string_lower(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("lower", args, 0, 0)?;
+    Ok(string_val(as_str_raw!(*receiver).to_lowercase()))
+}
+
+fn string_trim(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("trim", args, 0, 0)?;
+    Ok(string_val(String::from(as_str_raw!(*receiver).trim())))
+}
+
+fn string_starts_with(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("startsWith", args, 1, 1)?;
+    let prefix = string_arg("startsWith", args, 0)?;
+    Ok(boolean_val!(as_str_raw!(*receiver).starts_with(prefix.as_str())))
+}
+
+fn string_chars(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("chars", args, 0, 0)?;
+    let chars = as_str_raw!(*receiver).chars()
+        .map(|ch| string_val(ch.to_string())).collect();
+    Ok(list_val(chars))
+}
+
+fn list_len(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity("len", args, 0, 0)?;
+    Ok(number_val!(as_list!(*receiver).get_values().len() as f64))
+}
@@ -1,4 +1,10 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use mopa::{Any, mopafy};
+use num_bigint::BigInt;
+
+use crate::value::Value;
+use crate::native::NativeFn;
 
 
 pub trait  Obj: ObjClone + Any  {
@@ -26,7 +32,14 @@ impl Clone for Box<dyn Obj> {
 #[derive(Clone, Copy, PartialEq, Hash, Eq)]
 #[allow(non_camel_case_types)]
 pub enum ObjType {
-    OBJ_STRING
+    OBJ_STRING,
+    OBJ_ENUM,
+    OBJ_ENUM_VARIANT,
+    OBJ_BIGINT,
+    OBJ_LIST,
+    OBJ_NATIVE,
+    OBJ_RECORD,
+    OBJ_RECORD_INSTANCE
 }
 
 #[derive(Clone, Hash, PartialEq, Eq)]
@@ -55,3 +68,292 @@ impl ObjString {
         &self.string
     }
 }
+
+// An integer too large to be represented exactly by a number
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct ObjBigInt {
+    obj: ObjType,
+    value: BigInt
+}
+
+impl From<BigInt> for ObjBigInt {
+    fn from(value: BigInt) -> Self {
+        ObjBigInt {
+            obj: ObjType::OBJ_BIGINT,
+            value
+        }
+    }
+}
+
+impl Obj for ObjBigInt {
+    fn get_type(&self) -> &ObjType {
+        &self.obj
+    }
+}
+
+impl ObjBigInt {
+    pub fn get_value(&self) -> &BigInt {
+        &self.value
+    }
+}
+
+#[derive(Clone)]
+pub struct ObjList {
+    obj: ObjType,
+    values: Vec<Value>,
+    frozen: bool
+}
+
+impl From<Vec<Value>> for ObjList {
+    fn from(values: Vec<Value>) -> Self {
+        ObjList {
+            obj: ObjType::OBJ_LIST,
+            values,
+            frozen: false
+        }
+    }
+}
+
+impl Obj for ObjList {
+    fn get_type(&self) -> &ObjType {
+        &self.obj
+    }
+}
+
+impl ObjList {
+    pub fn get_values(&self) -> &Vec<Value> {
+        &self.values
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn freeze(&mut self) {
+        self.frozen = true
+    }
+}
+
+// A function implemented in rust, bound to a global name
+#[derive(Clone)]
+pub struct ObjNative {
+    obj: ObjType,
+    name: String,
+    function: NativeFn
+}
+
+impl Obj for ObjNative {
+    fn get_type(&self) -> &ObjType {
+        &self.obj
+    }
+}
+
+impl ObjNative {
+    pub fn new(name: String, function: NativeFn) -> Self {
+        ObjNative {
+            obj: ObjType::OBJ_NATIVE,
+            name,
+            function
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_function(&self) -> NativeFn {
+        self.function
+    }
+}
+
+// A single case of an enum declaration, along with the names of its associated values
+#[derive(Clone, PartialEq)]
+pub struct EnumVariant {
+    name: String,
+    fields: Vec<String>
+}
+
+impl EnumVariant {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_arity(&self) -> usize {
+        self.fields.len()
+    }
+}
+
+// every enum declaration gets its own id, so that two enums sharing a name
+// in different scopes (or different evals) are never equal
+static NEXT_ENUM_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, PartialEq)]
+pub struct ObjEnum {
+    obj: ObjType,
+    id: usize,
+    name: String,
+    variants: Vec<EnumVariant>
+}
+
+impl Obj for ObjEnum {
+    fn get_type(&self) -> &ObjType {
+        &self.obj
+    }
+}
+
+impl ObjEnum {
+    pub fn new(name: String) -> Self {
+        ObjEnum {
+            obj: ObjType::OBJ_ENUM,
+            id: NEXT_ENUM_ID.fetch_add(1, Ordering::Relaxed),
+            name,
+            variants: Vec::new()
+        }
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_variant(&self, name: &str) -> Option<&EnumVariant> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    pub fn add_variant(&mut self, name: String, fields: Vec<String>) {
+        self.variants.push(EnumVariant { name, fields });
+    }
+}
+
+// A value of an enum, e.g Color.Red or Color.Blue(0, 0, 255)
+#[derive(Clone)]
+pub struct ObjEnumVariant {
+    obj: ObjType,
+    enum_id: usize,
+    enum_name: String,
+    name: String,
+    values: Vec<Value>
+}
+
+impl Obj for ObjEnumVariant {
+    fn get_type(&self) -> &ObjType {
+        &self.obj
+    }
+}
+
+impl ObjEnumVariant {
+    pub fn new(enum_obj: &ObjEnum, name: String, values: Vec<Value>) -> Self {
+        ObjEnumVariant {
+            obj: ObjType::OBJ_ENUM_VARIANT,
+            enum_id: enum_obj.get_id(),
+            enum_name: String::from(enum_obj.get_name()),
+            name,
+            values
+        }
+    }
+
+    pub fn get_enum_id(&self) -> usize {
+        self.enum_id
+    }
+
+    pub fn get_enum_name(&self) -> &str {
+        &self.enum_name
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_values(&self) -> &Vec<Value> {
+        &self.values
+    }
+}
+
+// A record declaration, e.g record Point(x, y);
+#[derive(Clone, PartialEq)]
+pub struct ObjRecord {
+    obj: ObjType,
+    name: String,
+    fields: Vec<String>
+}
+
+impl Obj for ObjRecord {
+    fn get_type(&self) -> &ObjType {
+        &self.obj
+    }
+}
+
+impl ObjRecord {
+    pub fn new(name: String, fields: Vec<String>) -> Self {
+        ObjRecord {
+            obj: ObjType::OBJ_RECORD,
+            name,
+            fields
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_fields(&self) -> &Vec<String> {
+        &self.fields
+    }
+}
+
+// A value built by a record constructor, e.g Point(1, 2)
+#[derive(Clone)]
+pub struct ObjRecordInstance {
+    obj: ObjType,
+    record_name: String,
+    fields: Vec<String>,
+    values: Vec<Value>
+}
+
+impl Obj for ObjRecordInstance {
+    fn get_type(&self) -> &ObjType {
+        &self.obj
+    }
+}
+
+impl ObjRecordInstance {
+    pub fn new(record: &ObjRecord, values: Vec<Value>) -> Self {
+        ObjRecordInstance {
+            obj: ObjType::OBJ_RECORD_INSTANCE,
+            record_name: String::from(record.get_name()),
+            fields: record.get_fields().clone(),
+            values
+        }
+    }
+
+    pub fn get_record_name(&self) -> &str {
+        &self.record_name
+    }
+
+    pub fn get_fields(&self) -> &Vec<String> {
+        &self.fields
+    }
+
+    pub fn get_values(&self) -> &Vec<Value> {
+        &self.values
+    }
+
+    pub fn get_field(&self, name: &str) -> Option<&Value> {
+        let index = self.fields.iter().position(|field| field == name)?;
+        self.values.get(index)
+    }
+
+    // replace the value of a field, false if the record has no such field
+    pub fn set_field(&mut self, name: &str, value: Value) -> bool {
+        match self.fields.iter().position(|field| field == name) {
+            Some(index) => {
+                self.values[index] = value;
+                true
+            },
+            None => false
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::object::ObjString;
 use crate::scanner::Token;
 use crate::{chunk::Opcode, compiler::CompilerParser, scanner::TokenType};
-use crate::value::{ValueType, Value, AsValue};
+use num_bigint::BigInt;
+use crate::value::{ValueType, Value, AsValue, integer_val};
+use crate::types::StaticType;
 
 
 type ParseType = fn(&mut CompilerParser<'_>, bool) -> ();
@@ -12,15 +14,38 @@ type ParseType = fn(&mut CompilerParser<'_>, bool) -> ();
 pub enum Precedence {
     PREC_NONE = 0,
     PREC_ASSIGNMENT = 1,
-    PREC_OR = 2,
-    PREC_AND = 3,
-    PREC_EQUAL = 4,
-    PREC_COMPARISON = 5,
-    PREC_TERM = 6,
-    PREC_FACTOR = 7,
-    PREC_UNARY = 8,
-    PREC_CALL = 9,
-    PREC_PRIMARY = 10
+    PREC_PIPE = 2,
+    PREC_OR = 3,
+    PREC_AND = 4,
+    PREC_EQUAL = 5,
+    PREC_IS = 6,
+    PREC_COMPARISON = 7,
+    PREC_TERM = 8,
+    PREC_FACTOR = 9,
+    PREC_UNARY = 10,
+    PREC_CALL = 11,
+    PREC_PRIMARY = 12
+}
+
+impl Precedence {
+    // the next higher level, used to make binary operators left associative
+    pub fn next(&self) -> Precedence {
+        match *self {
+            Precedence::PREC_NONE => Precedence::PREC_ASSIGNMENT,
+            Precedence::PREC_ASSIGNMENT => Precedence::PREC_PIPE,
+            Precedence::PREC_PIPE => Precedence::PREC_OR,
+            Precedence::PREC_OR => Precedence::PREC_AND,
+            Precedence::PREC_AND => Precedence::PREC_EQUAL,
+            Precedence::PREC_EQUAL => Precedence::PREC_IS,
+            Precedence::PREC_IS => Precedence::PREC_COMPARISON,
+            Precedence::PREC_COMPARISON => Precedence::PREC_TERM,
+            Precedence::PREC_TERM => Precedence::PREC_FACTOR,
+            Precedence::PREC_FACTOR => Precedence::PREC_UNARY,
+            Precedence::PREC_UNARY => Precedence::PREC_CALL,
+            Precedence::PREC_CALL => Precedence::PREC_PRIMARY,
+            Precedence::PREC_PRIMARY => Precedence::PREC_PRIMARY
+        }
+    }
 }
 
 pub struct ParseRule {
@@ -61,9 +86,9 @@ pub fn get_rule(token_type: &TokenType) -> ParseRule {
         },
         TokenType::LEFT_PAREN => {
             ParseRule {
-                precedence: Precedence::PREC_NONE,
+                precedence: Precedence::PREC_CALL,
                 prefix: Some(parse_grouping),
-                infix: None
+                infix: Some(parse_call)
             }
         },
         TokenType::RIGHT_PAREN => {
@@ -80,6 +105,13 @@ pub fn get_rule(token_type: &TokenType) -> ParseRule {
                 infix: None
             }
         },
+        TokenType::LEFT_BRACKET => {
+            ParseRule {
+                precedence: Precedence::PREC_CALL,
+                prefix: Some(parse_list),
+                infix: Some(parse_index)
+            }
+        },
         TokenType::RIGHT_BRACE => {
             ParseRule {
                 precedence: Precedence::PREC_NONE,
@@ -96,9 +128,16 @@ pub fn get_rule(token_type: &TokenType) -> ParseRule {
         },
         TokenType::DOT => {
             ParseRule {
-                precedence: Precedence::PREC_NONE,
+                precedence: Precedence::PREC_CALL,
                 prefix: None,
-                infix: None
+                infix: Some(parse_dot)
+            }
+        },
+        TokenType::PIPE_GREATER => {
+            ParseRule {
+                precedence: Precedence::PREC_PIPE,
+                prefix: None,
+                infix: Some(parse_pipe)
             }
         },
         TokenType::MINUS => {
@@ -129,6 +168,13 @@ pub fn get_rule(token_type: &TokenType) -> ParseRule {
                 infix: Some(parse_binary)
             }
         },
+        TokenType::STAR => {
+            ParseRule {
+                precedence: Precedence::PREC_FACTOR,
+                prefix: None,
+                infix: Some(parse_binary)
+            }
+        },
         TokenType::NOT => {
             ParseRule {
                 precedence: Precedence::PREC_NONE,
@@ -150,6 +196,13 @@ pub fn get_rule(token_type: &TokenType) -> ParseRule {
                 infix: None
             }
         },
+        TokenType::IS => {
+            ParseRule {
+                precedence: Precedence::PREC_IS,
+                prefix: None,
+                infix: Some(parse_is)
+            }
+        },
         TokenType::EQUAL_EQUAL => {
             ParseRule {
                 precedence: Precedence::PREC_EQUAL,
@@ -297,6 +350,13 @@ pub fn get_rule(token_type: &TokenType) -> ParseRule {
                 infix: None
             }
         },
+        TokenType::FREEZE => {
+            ParseRule {
+                precedence: Precedence::PREC_NONE,
+                prefix: Some(parse_freeze),
+                infix: None
+            }
+        },
         TokenType::VAR => {
             ParseRule {
                 precedence: Precedence::PREC_NONE,
@@ -334,22 +394,68 @@ pub fn get_rule(token_type: &TokenType) -> ParseRule {
 fn parse_binary(compiler: &mut CompilerParser, _can_assign: bool) -> () {
     let op = (*compiler.get_prev().get_type()).clone();
     let rule = get_rule(&op);
-    compiler.parse_precedence(&rule.precedence);
+    let left = compiler.take_last_type();
+    compiler.parse_precedence(&rule.precedence.next());
+    let right = compiler.take_last_type();
+    compiler.check_binary(&op, &left, &right);
     match op {
-        TokenType::PLUS => compiler.emit_byte(TokenType::PLUS as u8),
-        TokenType::MINUS => compiler.emit_byte(TokenType::MINUS as u8),
-        TokenType::STAR => compiler.emit_byte(TokenType::STAR as u8),
-        TokenType::SLASH => compiler.emit_byte(TokenType::SLASH as u8),
-        TokenType::NOT_EQUAL => compiler.emit_bytes( TokenType::EQUAL as u8, TokenType::NOT as u8),
-        TokenType::EQUAL_EQUAL => compiler.emit_byte(TokenType::EQUAL as u8),
-        TokenType::GREATER => compiler.emit_byte(TokenType::GREATER as u8),
-        TokenType::GREATER_EQUAL => compiler.emit_bytes( TokenType::LESS as u8, TokenType::NOT as u8),
-        TokenType::LESS => compiler.emit_byte( TokenType::LESS as u8),
-        TokenType::LESS_EQUAL => compiler.emit_bytes( TokenType::GREATER as u8, TokenType::NOT as u8),
+        TokenType::PLUS => compiler.emit_byte(Opcode::OP_ADD as u8),
+        TokenType::MINUS => compiler.emit_byte(Opcode::OP_SUBTRACT as u8),
+        TokenType::STAR => compiler.emit_byte(Opcode::OP_MULTIPLY as u8),
+        TokenType::SLASH => compiler.emit_byte(Opcode::OP_DIVIDE as u8),
+        TokenType::NOT_EQUAL => compiler.emit_bytes( Opcode::OP_EQUAL as u8, Opcode::OP_NOT as u8),
+        TokenType::EQUAL_EQUAL => compiler.emit_byte(Opcode::OP_EQUAL as u8),
+        TokenType::GREATER => compiler.emit_byte(Opcode::OP_GREATER as u8),
+        TokenType::GREATER_EQUAL => compiler.emit_bytes( Opcode::OP_LESS as u8, Opcode::OP_NOT as u8),
+        TokenType::LESS => compiler.emit_byte( Opcode::OP_LESS as u8),
+        TokenType::LESS_EQUAL => compiler.emit_bytes( Opcode::OP_GREATER as u8, Opcode::OP_NOT as u8),
         _ => ()
     }
 }
 
+// value is Type, true when type(value) names Type. a variant is also
+// an instance of its enum and every value is Any
+fn parse_is(compiler: &mut CompilerParser, _can_assign: bool) -> () {
+    compiler.take_last_type();
+    compiler.consume(&TokenType::IDENTIFIER, "Expect type name after 'is'.");
+    let name = compiler.prev;
+    if !compiler.is_type_name(name.get_sized_content()) {
+        compiler.error(&format!("Unknown type {}", name.get_sized_content()));
+    }
+    let name_constant = compiler.identifier_constant(&name);
+    compiler.emit_bytes(Opcode::OP_IS as u8, name_constant);
+    compiler.set_last_type(StaticType::TYPE_BOOL);
+}
+
+// freeze(value) makes a list read only and gives it back. it is compiled
+// rather than called, so it can't be shadowed or aliased. lists are copied
+// values, so freezing a variable stores the frozen list back into it, and a
+// list literal made only of constants is frozen into a single constant
+fn parse_freeze(compiler: &mut CompilerParser, _can_assign: bool) -> () {
+    compiler.consume(&TokenType::LEFT_PAREN, "Expect '(' after freeze.");
+    let mark = compiler.chunk_mark();
+    compiler.expression();
+    let variable = compiler.resolved_variable();
+    let operand = compiler.take_last_type();
+    compiler.consume(&TokenType::RIGHT_PAREN, "Expect ')' after value.");
+    if !compiler.fold_frozen_list(mark) {
+        compiler.emit_byte(Opcode::OP_FREEZE as u8);
+        if let Some((set_op, arg)) = variable {
+            compiler.emit_bytes(set_op, arg);
+        }
+    }
+    compiler.set_last_type(operand);
+}
+
+fn parse_call(compiler: &mut CompilerParser, _can_assign: bool) -> () {
+    compiler.take_last_type();
+    match compiler.argument_list(0) {
+        Some(arg_count) => compiler.emit_bytes(Opcode::OP_CALL as u8, arg_count),
+        None => compiler.emit_byte(Opcode::OP_CALL_LIST as u8)
+    }
+    compiler.set_last_type(StaticType::TYPE_ANY);
+}
+
 fn parse_grouping(compiler: &mut CompilerParser, _can_assign: bool) -> () {
     compiler.expression();
     compiler.consume(&TokenType::RIGHT_PAREN, "Expect ')' after expression");
@@ -357,8 +463,16 @@ fn parse_grouping(compiler: &mut CompilerParser, _can_assign: bool) -> () {
 }
 
 fn parse_number(compiler: &mut CompilerParser, _can_assign: bool) -> ()  {
-    let val = compiler.get_prev().get_sized_content().parse::<f64>().unwrap();
-    compiler.emit_constant(number_val!(val));
+    let content = compiler.get_prev().get_sized_content();
+    // integer literals too long for a number become bigints
+    if compiler.get_bigint() && !content.contains('.') {
+        let val = content.parse::<BigInt>().unwrap();
+        compiler.emit_constant(integer_val(val));
+    } else {
+        let val = content.parse::<f64>().unwrap();
+        compiler.emit_constant(number_val!(val));
+    }
+    compiler.set_last_type(StaticType::TYPE_NUMBER);
 }
 
 fn parse_variable(compiler: &mut CompilerParser, can_assign: bool) -> () {
@@ -368,7 +482,8 @@ fn parse_variable(compiler: &mut CompilerParser, can_assign: bool) -> () {
 
 fn named_variable(compiler: &mut CompilerParser, name: &Token, can_assign: bool) -> () {
     let get_op: Opcode; let set_op: Opcode;
-    let mut arg = compiler.resolve_local( name);
+    let local = compiler.resolve_local( name);
+    let mut arg = local;
     if arg != -1 {
         get_op = Opcode::OP_GET_LOCAL;
         set_op = Opcode::OP_SET_LOCAL;
@@ -378,17 +493,27 @@ fn named_variable(compiler: &mut CompilerParser, name: &Token, can_assign: bool)
         set_op = Opcode::OP_SET_GLOBAL;
     }
 
+    let declared = compiler.variable_type(name, local);
     if can_assign && compiler.match_(TokenType::EQUAL) {
         compiler.expression();
+        let value_type = compiler.take_last_type();
+        compiler.check_assignment(name, &declared, &value_type);
         compiler.emit_bytes(set_op as u8, arg as u8);
+        compiler.set_last_type(value_type);
     } else {
         compiler.emit_bytes(get_op as u8, arg as u8);
+        compiler.mark_enum_ref(name);
+        compiler.mark_variable_ref(set_op as u8, arg as u8);
+        compiler.set_last_type(declared);
     }
 }
 
 fn parse_unary(compiler: &mut CompilerParser, _can_assign: bool) -> () {
-    compiler.expression();
-    match *compiler.get_prev().get_type() {
+    let op = *compiler.get_prev().get_type();
+    compiler.parse_precedence(&Precedence::PREC_UNARY);
+    let operand = compiler.take_last_type();
+    compiler.check_unary(&op, &operand);
+    match op {
         TokenType::MINUS => compiler.emit_byte(Opcode::OP_NEGATE as u8),
         TokenType::NOT => compiler.emit_byte(Opcode::OP_NOT as u8),
         _ => ()
@@ -397,16 +522,181 @@ fn parse_unary(compiler: &mut CompilerParser, _can_assign: bool) -> () {
 
 fn parse_literal(compiler: &mut CompilerParser, _can_assign: bool) -> () {
     match *compiler.get_prev().get_type() {
-        TokenType::FALSE => compiler.emit_byte(TokenType::FALSE as u8),
-        TokenType::TRUE => compiler.emit_byte(TokenType::TRUE as u8),
-        TokenType::NIL => compiler.emit_byte(TokenType::NIL as u8),
+        TokenType::FALSE => compiler.emit_byte(Opcode::OP_FALSE as u8),
+        TokenType::TRUE => compiler.emit_byte(Opcode::OP_TRUE as u8),
+        TokenType::NIL => compiler.emit_byte(Opcode::OP_NIL as u8),
         _ => ()
     }
+    match *compiler.get_prev().get_type() {
+        TokenType::NIL => compiler.set_last_type(StaticType::TYPE_NIL),
+        _ => compiler.set_last_type(StaticType::TYPE_BOOL)
+    }
 }
 
 fn parse_string(compiler: &mut CompilerParser, _can_assign: bool) -> () {
     let val = compiler.get_prev().get_sized_content();
     let val = &val[1..val.len() - 1];//to trim ""
     let obj_str = ObjString::from(String::from(val));
-    compiler.emit_constant(obj_val!(Box::from(obj_str)))
+    compiler.emit_constant(obj_val!(Box::from(obj_str)));
+    compiler.set_last_type(StaticType::TYPE_STRING);
+}
+
+fn parse_dot(compiler: &mut CompilerParser, _can_assign: bool) -> () {
+    let receiver = compiler.resolved_enum();
+    compiler.consume(&TokenType::IDENTIFIER, "Expect property name after '.'");
+    let name = compiler.prev;
+    let name_constant = compiler.identifier_constant(&name);
+    if !compiler.match_(TokenType::LEFT_PAREN) {
+        compiler.check_enum_variant(&receiver, &name, None);
+        compiler.emit_bytes(Opcode::OP_GET_PROPERTY as u8, name_constant);
+    } else if receiver.is_none() && name.get_sized_content() == "with"
+        && compiler.check(TokenType::IDENTIFIER) && compiler.check_next(TokenType::COLON) {
+        // only with(field: value, ...) copies a record, any other with(...)
+        // is an ordinary method call
+        with_arguments(compiler);
+    } else {
+        match compiler.argument_list(0) {
+            Some(arg_count) => {
+                compiler.check_enum_variant(&receiver, &name, Some(arg_count));
+                compiler.emit_bytes(Opcode::OP_INVOKE as u8, name_constant);
+                compiler.emit_byte(arg_count);
+            },
+            // the arity of a spread call is only known at runtime
+            None => compiler.emit_bytes(Opcode::OP_INVOKE_LIST as u8, name_constant)
+        }
+    }
+    match receiver {
+        Some(enum_obj) => compiler.set_last_type(StaticType::TYPE_ENUM(String::from(enum_obj.get_name()))),
+        None => compiler.set_last_type(StaticType::TYPE_ANY)
+    }
+}
+
+// value |> f(args) calls f(value, args) and value |> target.method(args)
+// calls target.method(value, args). without arguments, value |> f and
+// value |> target.method call them with value alone
+fn parse_pipe(compiler: &mut CompilerParser, _can_assign: bool) -> () {
+    compiler.take_last_type();
+    compiler.consume(&TokenType::IDENTIFIER, "Expect a function or method after '|>'.");
+    let target = compiler.prev;
+    named_variable(compiler, &target, false);
+    if !compiler.check(TokenType::DOT) {
+        // the piped value goes above the callee, as the first argument
+        compiler.emit_byte(Opcode::OP_SWAP as u8);
+        let arg_count = if compiler.match_(TokenType::LEFT_PAREN) { compiler.argument_list(1) } else { Some(1) };
+        match arg_count {
+            Some(arg_count) => compiler.emit_bytes(Opcode::OP_CALL as u8, arg_count),
+            None => compiler.emit_byte(Opcode::OP_CALL_LIST as u8)
+        }
+        compiler.set_last_type(StaticType::TYPE_ANY);
+        return;
+    }
+    let mut receiver;
+    let mut name;
+    loop {
+        receiver = compiler.resolved_enum();
+        compiler.consume(&TokenType::DOT, "Expect '.' before the method name after '|>'.");
+        compiler.consume(&TokenType::IDENTIFIER, "Expect method name after '.'");
+        name = compiler.prev;
+        if !compiler.check(TokenType::DOT) {
+            break;
+        }
+        let name_constant = compiler.identifier_constant(&name);
+        compiler.emit_bytes(Opcode::OP_GET_PROPERTY as u8, name_constant);
+    }
+    let name_constant = compiler.identifier_constant(&name);
+    // the piped value goes above the receiver, as the first argument
+    compiler.emit_byte(Opcode::OP_SWAP as u8);
+    let arg_count = if compiler.match_(TokenType::LEFT_PAREN) { compiler.argument_list(1) } else { Some(1) };
+    match arg_count {
+        Some(arg_count) => {
+            compiler.check_enum_variant(&receiver, &name, Some(arg_count));
+            compiler.emit_bytes(Opcode::OP_INVOKE as u8, name_constant);
+            compiler.emit_byte(arg_count);
+        },
+        None => compiler.emit_bytes(Opcode::OP_INVOKE_LIST as u8, name_constant)
+    }
+    match receiver {
+        Some(enum_obj) => compiler.set_last_type(StaticType::TYPE_ENUM(String::from(enum_obj.get_name()))),
+        None => compiler.set_last_type(StaticType::TYPE_ANY)
+    }
+}
+
+// record.with(field: value, ...), every field name is pushed as a
+// string followed by its new value
+fn with_arguments(compiler: &mut CompilerParser) {
+    let mut count: u8 = 0;
+    loop {
+        compiler.consume(&TokenType::IDENTIFIER, "Expect field name.");
+        let field = compiler.prev;
+        let field_constant = compiler.identifier_constant(&field);
+        compiler.emit_bytes(Opcode::OP_CONSTANT as u8, field_constant);
+        compiler.consume(&TokenType::COLON, "Expect ':' after field name.");
+        compiler.expression();
+        if count == u8::MAX {
+            compiler.error("Can't have more than 255 fields in with.");
+        } else {
+            count += 1;
+        }
+        if !compiler.match_(TokenType::COMMA) {
+            break;
+        }
+    }
+    compiler.consume(&TokenType::RIGHT_PAREN, "Expect ')' after fields.");
+    compiler.emit_bytes(Opcode::OP_WITH as u8, count);
+}
+
+fn parse_list(compiler: &mut CompilerParser, _can_assign: bool) -> () {
+    let count = compiler.element_list(TokenType::RIGHT_BRACKET, 0, "Can't have more than 255 elements in a list literal.");
+    compiler.consume(&TokenType::RIGHT_BRACKET, "Expect ']' after list elements.");
+    if let Some(count) = count {
+        compiler.emit_bytes(Opcode::OP_BUILD_LIST as u8, count);
+    }
+    compiler.set_last_type(StaticType::TYPE_LIST);
+}
+
+// a missing slice bound is passed to the vm as nil
+fn slice_bound(compiler: &mut CompilerParser) {
+    if compiler.check(TokenType::COLON) || compiler.check(TokenType::RIGHT_BRACKET) {
+        compiler.emit_byte(Opcode::OP_NIL as u8);
+    } else {
+        compiler.expression();
+    }
+}
+
+// x[i], x[start:end:step] and, on a variable, x[start:end:step] = list
+fn parse_index(compiler: &mut CompilerParser, can_assign: bool) -> () {
+    let target = compiler.resolved_variable();
+    let receiver_type = compiler.take_last_type();
+    slice_bound(compiler);
+    if !compiler.match_(TokenType::COLON) {
+        compiler.consume(&TokenType::RIGHT_BRACKET, "Expect ']' after index.");
+        compiler.emit_byte(Opcode::OP_GET_INDEX as u8);
+        compiler.set_last_type(StaticType::TYPE_ANY);
+        return;
+    }
+    slice_bound(compiler);
+    if compiler.match_(TokenType::COLON) {
+        slice_bound(compiler);
+    } else {
+        compiler.emit_byte(Opcode::OP_NIL as u8);
+    }
+    compiler.consume(&TokenType::RIGHT_BRACKET, "Expect ']' after slice.");
+
+    if can_assign && compiler.match_(TokenType::EQUAL) {
+        match target {
+            Some((set_op, arg)) => {
+                compiler.expression();
+                compiler.emit_byte(Opcode::OP_SET_SLICE as u8);
+                compiler.emit_bytes(set_op, arg);
+            },
+            None => compiler.error("Only slices of variables can be assigned")
+        }
+        compiler.set_last_type(StaticType::TYPE_LIST);
+    } else {
+        compiler.emit_byte(Opcode::OP_SLICE as u8);
+        match receiver_type {
+            StaticType::TYPE_STRING | StaticType::TYPE_LIST => compiler.set_last_type(receiver_type),
+            _ => compiler.set_last_type(StaticType::TYPE_ANY)
+        }
+    }
 }
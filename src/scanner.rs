@@ -3,8 +3,8 @@
 #[derive(Debug, PartialEq, Clone, Copy)]
 #[allow(non_camel_case_types)]
 pub enum TokenType {
-    LEFT_PAREN, RIGHT_PAREN, LEFT_BRACE, RIGHT_BRACE, COMMA, DOT,
-    SLASH, STAR, PLUS, MINUS, SEMICOLON,
+    LEFT_PAREN, RIGHT_PAREN, LEFT_BRACE, RIGHT_BRACE, LEFT_BRACKET, RIGHT_BRACKET, COMMA, DOT,
+    ELLIPSIS, SLASH, STAR, PLUS, MINUS, SEMICOLON, COLON, PIPE_GREATER,
     
     NOT, NOT_EQUAL, LESS, LESS_EQUAL, EQUAL, EQUAL_EQUAL, GREATER,
     GREATER_EQUAL,
@@ -12,14 +12,15 @@ pub enum TokenType {
     IDENTIFIER, STRING, NUMBER,
 
     TRUE, FALSE, AND, OR, NIL, WHILE, FOR, CLASS, 
-    IF, ELSE, RETURN, VAR, SUPER, THIS, FUN, PRINT,
+    IF, ELSE, RETURN, VAR, SUPER, THIS, FUN, PRINT, ENUM, IS, RECORD, FREEZE,
 
-    EOF, ERROR
+    DOC_COMMENT, EOF, ERROR
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct Token<'a> {
     line: usize,
+    column: usize,
     content: &'a str,
     content_len: usize,
     content_start: usize,
@@ -30,6 +31,7 @@ impl<'a> Token<'a> {
     pub fn  init_token(content: &'a str) -> Self {
         Token {
             line: 0,
+            column: 0,
             content,
             content_len: 0,
             content_start: 0,
@@ -39,11 +41,15 @@ impl<'a> Token<'a> {
     pub fn get_line(&self) -> usize {
         self.line
     }
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
     
     #[allow(dead_code)]
     pub fn get_len(&self) -> usize {
         self.content_len
     }
+    #[allow(dead_code)]
     pub fn get_start(&self) -> usize {
         self.content_start
     }
@@ -58,6 +64,9 @@ impl<'a> Token<'a> {
     pub fn set_line(&mut self, line: usize)  {
         self.line = line
     }
+    pub fn set_column(&mut self, column: usize)  {
+        self.column = column
+    }
     pub fn set_len(&mut self, len: usize) {
         self.content_len = len
     }
@@ -69,18 +78,23 @@ impl<'a> Token<'a> {
     }
 }
 
+// current and start are byte offsets into content, line and column count
+// characters so they can be reported to the user
 #[derive(Clone, Copy)]
 pub struct Scanner<'a> {
     line: usize,
+    column: usize,
     content: &'a str,
     current: usize,
     start: usize,
+    start_line: usize,
+    start_column: usize
 }
 
 impl<'a> Scanner<'a> {
 
     pub fn init_scanner(content: &'a str) -> Self {
-        Scanner { line: 1, content, current: 0, start: 0 }
+        Scanner { line: 1, column: 1, content, current: 0, start: 0, start_line: 1, start_column: 1 }
     }
 
     fn make_token(&self, kind: TokenType) -> Token<'a> {
@@ -88,7 +102,8 @@ impl<'a> Scanner<'a> {
         token.set_type(kind);
         token.set_start(self.start);
         token.set_len(self.current - self.start);
-        token.set_line(self.line);
+        token.set_line(self.start_line);
+        token.set_column(self.start_column);
         token
     }
 
@@ -96,13 +111,22 @@ impl<'a> Scanner<'a> {
         let mut token = Token::init_token(message);
         token.set_start(0);
         token.set_len(message.len());
-        token.set_line(self.line);
+        token.set_line(self.start_line);
+        token.set_column(self.start_column);
         token
     }
 
-    pub fn scan_token(&mut self) -> Token<'a> {
-        self.skip_white_spaces();
+    fn mark_start(&mut self) {
         self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+    }
+
+    pub fn scan_token(&mut self) -> Token<'a> {
+        if let Some(token) = self.skip_white_spaces() {
+            return token
+        }
+        self.mark_start();
         if self.is_at_end() {
             return self.make_token(TokenType::EOF)
         }
@@ -112,10 +136,21 @@ impl<'a> Scanner<'a> {
             ')' => self.make_token(TokenType::RIGHT_PAREN),
             '{' => self.make_token(TokenType::LEFT_BRACE),
             '}' => self.make_token(TokenType::RIGHT_BRACE),
+            '[' => self.make_token(TokenType::LEFT_BRACKET),
+            ']' => self.make_token(TokenType::RIGHT_BRACKET),
             ',' => self.make_token(TokenType::COMMA),
-            '.' => self.make_token(TokenType::DOT),
+            '.' => {
+                if self.peek_() == '.' && self.peek_next_() == '.' {
+                    self.advance();
+                    self.advance();
+                    self.make_token(TokenType::ELLIPSIS)
+                } else {
+                    self.make_token(TokenType::DOT)
+                }
+            },
             '-' => self.make_token(TokenType::MINUS),
             ';' => self.make_token(TokenType::SEMICOLON),
+            ':' => self.make_token(TokenType::COLON),
             '*' => self.make_token(TokenType::STAR),
             '+' => self.make_token(TokenType::PLUS),
             '/' => self.make_token(TokenType::SLASH),
@@ -147,9 +182,16 @@ impl<'a> Scanner<'a> {
                     self.make_token(TokenType::GREATER)
                 }
             },
+            '|' => {
+                if self.match_('>') {
+                    self.make_token(TokenType::PIPE_GREATER)
+                } else {
+                    self.error_token("Unexpected Character")
+                }
+            },
             '"' => self.string(),
             '0'..='9' => self.number(),
-            'a'..='z' | 'A'..='Z' | '_' => self.identifier(),
+            ch if ch.is_alphabetic() || ch == '_' => self.identifier(),
             _ => self.error_token("Unexpected Character")
         }
     }
@@ -158,9 +200,16 @@ impl<'a> Scanner<'a> {
         self.current >= self.content.len()
     }
 
+    // moves past the next character, keeping line and column in step
     fn advance(&mut self) -> char {
-        let val = self.content.chars().nth(self.current).unwrap();
-        self.current += 1;
+        let val = self.peek_();
+        self.current += val.len_utf8();
+        if val == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         val
     }
 
@@ -168,55 +217,91 @@ impl<'a> Scanner<'a> {
         if self.is_at_end() {
             return false
         }
-        if !(self.content.chars().nth(self.current ).unwrap() == expected) {
+        if !(self.peek_() == expected) {
             return false
         }
-        self.current += 1;
+        self.advance();
         return true;
     }
 
-    fn skip_white_spaces(&mut self) {
+    // skips white spaces and comments, a doc comment or an unterminated
+    // block comment is returned as a token instead
+    fn skip_white_spaces(&mut self) -> Option<Token<'a>> {
         loop {
             let ch = self.peek_();
             match ch {
-                ' ' | '\r' | '\t'  => {
-                    self.advance();
-                },
-                '\n' => {
-                    self.line += 1;
+                ' ' | '\r' | '\t' | '\n'  => {
                     self.advance();
                 },
                 '/' => {
                     if self.peek_next_() == '/' {
+                        let rest = &self.content[self.current..];
+                        let is_doc = rest.starts_with("///") && !rest.starts_with("////");
+                        self.mark_start();
                         while !self.is_at_end() && !(self.peek_() == '\n') {
                             self.advance();
                         }
+                        if is_doc {
+                            return Some(self.make_token(TokenType::DOC_COMMENT))
+                        }
+                    } else if self.peek_next_() == '*' {
+                        if !self.block_comment() {
+                            return Some(self.error_token("Unterminated block comment"))
+                        }
+                    } else {
+                        break
                     }
                 },
                 _ => break
             }
         }
+        None
     }
 
-    fn peek_(&self) -> char {
-        if self.is_at_end() {
-            return '\0';
+    // block comments nest, so every /* needs its own */
+    fn block_comment(&mut self) -> bool {
+        self.advance();
+        self.advance();
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                return false
+            }
+            if self.peek_() == '/' && self.peek_next_() == '*' {
+                self.advance();
+                self.advance();
+                depth += 1;
+            } else if self.peek_() == '*' && self.peek_next_() == '/' {
+                self.advance();
+                self.advance();
+                depth -= 1;
+            } else {
+                self.advance();
+            }
         }
-        return self.content.chars().nth(self.current).unwrap();
+        true
+    }
+
+    fn peek_(&self) -> char {
+        self.char_at(self.current)
     }
 
     fn peek_next_(&self) -> char {
-        if self.is_at_end() {
-            return '\0';
+        let mut chars = self.content.get(self.current..).unwrap_or("").chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
+    }
+
+    // character starting at a byte offset, '\0' past the end or inside a character
+    fn char_at(&self, offset: usize) -> char {
+        match self.content.get(offset..) {
+            Some(rest) => rest.chars().next().unwrap_or('\0'),
+            None => '\0'
         }
-        return self.content.chars().nth(self.current + 1).unwrap();
     }
 
     fn string(&mut self) -> Token<'a> {
         while !self.is_at_end() && self.peek_() != '"' {
-            if self.peek_() == '\n' {
-                self.current += 1;
-            }
             self.advance();
         }
         if self.is_at_end() {
@@ -247,24 +332,55 @@ impl<'a> Scanner<'a> {
     }
 
     fn identifier_type(&self ) -> TokenType {
-        match self.content.chars().nth(self.start).unwrap() {
+        match self.char_at(self.start) {
             'a' => self.check_keyword(1, 2, "and", TokenType::AND),
             'c' => self.check_keyword(1, 4, "class", TokenType::CLASS),
-            'e' => self.check_keyword(1, 3, "else", TokenType::ELSE),
-            'i' => self.check_keyword(1, 1, "if", TokenType::IF),
-            'n' => self.check_keyword(1, 2, "il", TokenType::NIL),
+            'e' => {
+                if self.current - self.start > 1 {
+                    match self.char_at(self.start + 1) {
+                        'l' =>  self.check_keyword(2, 2, "else", TokenType::ELSE),
+                        'n' =>  self.check_keyword(2, 2, "enum", TokenType::ENUM),
+                        _ => TokenType::IDENTIFIER
+                    }
+                }else {
+                    TokenType::IDENTIFIER
+                }
+            },
+            'i' => {
+                if self.current - self.start > 1 {
+                    match self.char_at(self.start + 1) {
+                        'f' =>  self.check_keyword(2, 0, "if", TokenType::IF),
+                        's' =>  self.check_keyword(2, 0, "is", TokenType::IS),
+                        _ => TokenType::IDENTIFIER
+                    }
+                }else {
+                    TokenType::IDENTIFIER
+                }
+            },
+            'n' => self.check_keyword(1, 2, "nil", TokenType::NIL),
             'o' => self.check_keyword(1, 1, "or", TokenType::OR),
             'p' => self.check_keyword(1, 4, "print", TokenType::PRINT),
-            'r' => self.check_keyword(1, 5, "return", TokenType::RETURN),
+            'r' => {
+                if self.current - self.start > 2 && self.char_at(self.start + 1) == 'e' {
+                    match self.char_at(self.start + 2) {
+                        't' =>  self.check_keyword(3, 3, "return", TokenType::RETURN),
+                        'c' =>  self.check_keyword(3, 3, "record", TokenType::RECORD),
+                        _ => TokenType::IDENTIFIER
+                    }
+                }else {
+                    TokenType::IDENTIFIER
+                }
+            },
             's' => self.check_keyword(1, 4, "super", TokenType::SUPER),
-            'v' => self.check_keyword(1, 2, "are", TokenType::VAR),
+            'v' => self.check_keyword(1, 2, "var", TokenType::VAR),
             'w' => self.check_keyword(1, 4, "while", TokenType::WHILE),
             'f' => {
                 if self.current - self.start > 1 {
-                    match self.content.chars().nth(self.start + 1).unwrap() {
+                    match self.char_at(self.start + 1) {
                         'a' =>  self.check_keyword(2, 3, "false", TokenType::FALSE),
                         'o' =>  self.check_keyword(2, 1, "for", TokenType::FOR),
                         'u' =>  self.check_keyword(2, 1, "fun", TokenType::FUN),
+                        'r' =>  self.check_keyword(2, 4, "freeze", TokenType::FREEZE),
                         _ => TokenType::IDENTIFIER
                     }
                 }else {
@@ -273,7 +389,7 @@ impl<'a> Scanner<'a> {
             },
             't' => {
                 if self.current - self.start > 1 {
-                    match self.content.chars().nth(self.start + 1).unwrap() {
+                    match self.char_at(self.start + 1) {
                         'h' =>  self.check_keyword(2, 2, "this", TokenType::THIS),
                         'r' =>  self.check_keyword(2, 2, "true", TokenType::TRUE),
                         _ => TokenType::IDENTIFIER
@@ -0,0 +1,91 @@
+use crate::scanner::TokenType;
+
+// How the compiler treats optional type annotations
+#[derive(Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum TypeMode {
+    TYPE_IGNORE, // annotations are parsed and thrown away
+    TYPE_CHECK,  // annotations and inferred types are checked at compile time
+    TYPE_STRICT  // like TYPE_CHECK, but annotated variables are also checked at runtime
+}
+
+// Static type of an expression as far as the compiler can tell
+#[derive(Clone, PartialEq, Debug)]
+#[allow(non_camel_case_types)]
+pub enum StaticType {
+    TYPE_ANY,
+    TYPE_NUMBER,
+    TYPE_STRING,
+    TYPE_BOOL,
+    TYPE_NIL,
+    TYPE_LIST,
+    TYPE_ENUM(String),
+    TYPE_RECORD(String)
+}
+
+impl StaticType {
+
+    // resolve a builtin type name, enum names are resolved by the compiler
+    pub fn from_name(name: &str) -> Option<StaticType> {
+        match name {
+            "Any" => Some(StaticType::TYPE_ANY),
+            "Number" => Some(StaticType::TYPE_NUMBER),
+            "String" => Some(StaticType::TYPE_STRING),
+            "Bool" => Some(StaticType::TYPE_BOOL),
+            "Nil" => Some(StaticType::TYPE_NIL),
+            "List" => Some(StaticType::TYPE_LIST),
+            _ => None
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            StaticType::TYPE_ANY => "Any",
+            StaticType::TYPE_NUMBER => "Number",
+            StaticType::TYPE_STRING => "String",
+            StaticType::TYPE_BOOL => "Bool",
+            StaticType::TYPE_NIL => "Nil",
+            StaticType::TYPE_LIST => "List",
+            StaticType::TYPE_ENUM(name) => name,
+            StaticType::TYPE_RECORD(name) => name
+        }
+    }
+
+    // whether a value of type other can be stored where self is expected
+    pub fn accepts(&self, other: &StaticType) -> bool {
+        *self == StaticType::TYPE_ANY || *other == StaticType::TYPE_ANY || self == other
+    }
+
+    pub fn unary_result(op: &TokenType, operand: &StaticType) -> Result<StaticType, &'static str> {
+        match *op {
+            TokenType::MINUS if StaticType::TYPE_NUMBER.accepts(operand) => Ok(StaticType::TYPE_NUMBER),
+            TokenType::MINUS => Err("Operand must be a number"),
+            _ => Ok(StaticType::TYPE_BOOL)
+        }
+    }
+
+    pub fn binary_result(op: &TokenType, left: &StaticType, right: &StaticType) -> Result<StaticType, &'static str> {
+        let numbers = StaticType::TYPE_NUMBER.accepts(left) && StaticType::TYPE_NUMBER.accepts(right);
+        match *op {
+            TokenType::PLUS => {
+                if *left == StaticType::TYPE_ANY || *right == StaticType::TYPE_ANY {
+                    Ok(StaticType::TYPE_ANY)
+                } else if *left == StaticType::TYPE_NUMBER && *right == StaticType::TYPE_NUMBER {
+                    Ok(StaticType::TYPE_NUMBER)
+                } else if *left == StaticType::TYPE_STRING && *right == StaticType::TYPE_STRING {
+                    Ok(StaticType::TYPE_STRING)
+                } else {
+                    Err("Operands must be two numbers or two strings")
+                }
+            },
+            TokenType::MINUS | TokenType::STAR | TokenType::SLASH => {
+                if numbers { Ok(StaticType::TYPE_NUMBER) } else { Err("Operands must be a number") }
+            },
+            TokenType::GREATER | TokenType::GREATER_EQUAL | TokenType::LESS | TokenType::LESS_EQUAL => {
+                let strings = StaticType::TYPE_STRING.accepts(left) && StaticType::TYPE_STRING.accepts(right);
+                if numbers || strings { Ok(StaticType::TYPE_BOOL) } else { Err("Operands must be two numbers or two strings") }
+            },
+            _ => Ok(StaticType::TYPE_BOOL)
+        }
+    }
+}
@@ -1,5 +1,10 @@
 #![macro_use]
-use crate::object::{Obj, ObjType, ObjString};
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive};
+use crate::object::{Obj, ObjType, ObjString, ObjEnum, ObjEnumVariant, ObjBigInt, ObjList, ObjNative, ObjRecord, ObjRecordInstance};
+
+// every integer up to this magnitude is exactly representable by a number
+pub const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
 
 
 #[derive(PartialEq, Clone, Copy)]
@@ -201,6 +206,167 @@ macro_rules! as_str {
     };
 }
 
+//is enum type
+macro_rules! is_enum {
+    ($value: expr) => {
+        {
+            is_obj_type(&$value, &ObjType::OBJ_ENUM)
+        }
+    };
+}
+
+//convert value to enum object
+macro_rules! as_enum {
+    ($value: expr) => {
+        {
+            let generic_val: Box<dyn Obj> = as_obj!($value);
+            let res: ObjEnum = match generic_val.downcast_ref::<ObjEnum>() {
+                Some(ele) => (*ele).clone(),
+                None => panic!("error")
+            };
+            res
+        }
+    };
+}
+
+//convert value to enum variant object
+macro_rules! as_enum_variant {
+    ($value: expr) => {
+        {
+            let generic_val: Box<dyn Obj> = as_obj!($value);
+            let res: ObjEnumVariant = match generic_val.downcast_ref::<ObjEnumVariant>() {
+                Some(ele) => (*ele).clone(),
+                None => panic!("error")
+            };
+            res
+        }
+    };
+}
+
+//is record declaration type
+macro_rules! is_record {
+    ($value: expr) => {
+        {
+            is_obj_type(&$value, &ObjType::OBJ_RECORD)
+        }
+    };
+}
+
+//convert value to record object
+macro_rules! as_record {
+    ($value: expr) => {
+        {
+            let generic_val: Box<dyn Obj> = as_obj!($value);
+            let res: ObjRecord = match generic_val.downcast_ref::<ObjRecord>() {
+                Some(ele) => (*ele).clone(),
+                None => panic!("error")
+            };
+            res
+        }
+    };
+}
+
+//is record instance type
+macro_rules! is_record_instance {
+    ($value: expr) => {
+        {
+            is_obj_type(&$value, &ObjType::OBJ_RECORD_INSTANCE)
+        }
+    };
+}
+
+//convert value to record instance object
+macro_rules! as_record_instance {
+    ($value: expr) => {
+        {
+            let generic_val: Box<dyn Obj> = as_obj!($value);
+            let res: ObjRecordInstance = match generic_val.downcast_ref::<ObjRecordInstance>() {
+                Some(ele) => (*ele).clone(),
+                None => panic!("error")
+            };
+            res
+        }
+    };
+}
+
+//is bigint type
+macro_rules! is_bigint {
+    ($value: expr) => {
+        {
+            is_obj_type(&$value, &ObjType::OBJ_BIGINT)
+        }
+    };
+}
+
+//convert value to bigint object
+macro_rules! as_bigint {
+    ($value: expr) => {
+        {
+            let generic_val: Box<dyn Obj> = as_obj!($value);
+            let res: ObjBigInt = match generic_val.downcast_ref::<ObjBigInt>() {
+                Some(ele) => (*ele).clone(),
+                None => panic!("error")
+            };
+            res
+        }
+    };
+}
+
+//is list type
+macro_rules! is_list {
+    ($value: expr) => {
+        {
+            is_obj_type(&$value, &ObjType::OBJ_LIST)
+        }
+    };
+}
+
+//convert value to list object
+macro_rules! as_list {
+    ($value: expr) => {
+        {
+            let generic_val: Box<dyn Obj> = as_obj!($value);
+            let res: ObjList = match generic_val.downcast_ref::<ObjList>() {
+                Some(ele) => (*ele).clone(),
+                None => panic!("error")
+            };
+            res
+        }
+    };
+}
+
+//is native function type
+macro_rules! is_native {
+    ($value: expr) => {
+        {
+            is_obj_type(&$value, &ObjType::OBJ_NATIVE)
+        }
+    };
+}
+
+//convert value to native function object
+macro_rules! as_native {
+    ($value: expr) => {
+        {
+            let generic_val: Box<dyn Obj> = as_obj!($value);
+            let res: ObjNative = match generic_val.downcast_ref::<ObjNative>() {
+                Some(ele) => (*ele).clone(),
+                None => panic!("error")
+            };
+            res
+        }
+    };
+}
+
+//is number or bigint
+macro_rules! is_numeric {
+    ($value: expr) => {
+        {
+            is_number!($value) || is_bigint!($value)
+        }
+    };
+}
+
 //convert value to string 
 macro_rules! as_str_raw {
     ($value: expr) => {
@@ -210,6 +376,54 @@ macro_rules! as_str_raw {
     };
 }
 
+// name of the runtime type of a value, as written in type annotations
+pub fn type_name(value: &Value) -> String {
+    match *value.get_type_ref() {
+        ValueType::VAL_BOOLEAN => String::from("Bool"),
+        ValueType::VAL_NIL => String::from("Nil"),
+        ValueType::VAL_NUMBER => String::from("Number"),
+        ValueType::VAL_OBJ => match *as_obj!(*value).get_type() {
+            ObjType::OBJ_STRING => String::from("String"),
+            ObjType::OBJ_ENUM => String::from("Enum"),
+            ObjType::OBJ_ENUM_VARIANT => String::from(as_enum_variant!(*value).get_enum_name()),
+            ObjType::OBJ_BIGINT => String::from("Number"),
+            ObjType::OBJ_LIST => String::from("List"),
+            ObjType::OBJ_NATIVE => String::from("Function"),
+            ObjType::OBJ_RECORD => String::from("Record"),
+            ObjType::OBJ_RECORD_INSTANCE => String::from(as_record_instance!(*value).get_record_name())
+        }
+    }
+}
+
+// exact integer held by a number or bigint, None for fractions and numbers
+// too large to be trusted as integers
+pub fn as_integer(value: &Value) -> Option<BigInt> {
+    if is_bigint!(*value) {
+        return Some(as_bigint!(*value).get_value().clone());
+    }
+    let number = as_number!(value);
+    if number.fract() != 0.0 || number.abs() > MAX_SAFE_INTEGER {
+        return None;
+    }
+    BigInt::from_f64(number)
+}
+
+// an integer as a number when it is exactly representable, otherwise as a bigint
+pub fn integer_val(value: BigInt) -> Value {
+    match value.to_f64() {
+        Some(number) if number.abs() <= MAX_SAFE_INTEGER => number_val!(number),
+        _ => obj_val!(Box::from(ObjBigInt::from(value)))
+    }
+}
+
+// numeric value as a number, bigints are rounded to the closest number
+pub fn as_float(value: &Value) -> f64 {
+    if is_bigint!(*value) {
+        return as_bigint!(*value).get_value().to_f64().unwrap_or(f64::NAN);
+    }
+    as_number!(value)
+}
+
 pub struct ValueArray {
     values: Vec<Value>
 }
@@ -230,23 +444,56 @@ impl ValueArray {
         &self.values
     }
 
-    fn print_obj(value: &Value) {
+    pub fn truncate(&mut self, len: usize) {
+        self.values.truncate(len);
+    }
+
+    fn stringify_obj(value: &Value) -> String {
         match *as_obj!(*value).get_type() {
-            ObjType::OBJ_STRING => println!("{}", as_str_raw!(*value))
+            ObjType::OBJ_STRING => String::from(as_str_raw!(*value)),
+            ObjType::OBJ_ENUM => format!("<enum {}>", as_enum!(*value).get_name()),
+            ObjType::OBJ_ENUM_VARIANT => {
+                let variant = as_enum_variant!(*value);
+                let mut res = format!("{}.{}", variant.get_enum_name(), variant.get_name());
+                if !variant.get_values().is_empty() {
+                    let values: Vec<String> = variant.get_values().iter()
+                        .map(ValueArray::stringify_value).collect();
+                    res = format!("{}({})", res, values.join(", "));
+                }
+                res
+            },
+            ObjType::OBJ_BIGINT => as_bigint!(*value).get_value().to_string(),
+            ObjType::OBJ_LIST => {
+                let values: Vec<String> = as_list!(*value).get_values().iter()
+                    .map(ValueArray::stringify_value).collect();
+                format!("[{}]", values.join(", "))
+            },
+            ObjType::OBJ_NATIVE => format!("<native fn {}>", as_native!(*value).get_name()),
+            ObjType::OBJ_RECORD => format!("<record {}>", as_record!(*value).get_name()),
+            ObjType::OBJ_RECORD_INSTANCE => {
+                let record = as_record_instance!(*value);
+                let fields: Vec<String> = record.get_fields().iter().zip(record.get_values().iter())
+                    .map(|(field, value)| format!("{}: {}", field, ValueArray::stringify_value(value))).collect();
+                format!("{}({})", record.get_record_name(), fields.join(", "))
+            }
         }
     }
 
-    pub fn print_value(value: &Value) {
+    pub fn stringify_value(value: &Value) -> String {
         match *value.get_type_ref() {
             ValueType::VAL_BOOLEAN =>
                 if let true =  (*value.get_as_ref()).get_boolean_ref().unwrap() { 
-                    println!("true")
+                    String::from("true")
                 }else {
-                    println!("false");
+                    String::from("false")
                 },
-            ValueType::VAL_NIL => println!("nill"),
-            ValueType::VAL_NUMBER => println!("{}", as_number!(value)),
-            ValueType::VAL_OBJ => ValueArray::print_obj(value)
+            ValueType::VAL_NIL => String::from("nill"),
+            ValueType::VAL_NUMBER => format!("{}", as_number!(value)),
+            ValueType::VAL_OBJ => ValueArray::stringify_obj(value)
         }
     }
+
+    pub fn print_value(value: &Value) {
+        println!("{}", ValueArray::stringify_value(value));
+    }
 }
@@ -1,11 +1,19 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
 use std::{convert::TryInto};
 use crate::compiler::CompilerParser;
 use crate::scanner::Scanner;
-pub(crate) use crate::value::{Value, ValueArray, ValueType, AsValue, is_obj_type};
+pub(crate) use crate::value::{Value, ValueArray, ValueType, AsValue, is_obj_type, type_name};
+use crate::value::{MAX_SAFE_INTEGER, as_integer, as_float, integer_val};
+use crate::types::TypeMode;
+use crate::common::DEBUG_PRINT_CODE;
 use crate::debug::{disassemble_chunk};
 use crate::chunk::{Chunk, Opcode};
-use crate::object::{ObjString, Obj, ObjType};
+use crate::object::{ObjString, Obj, ObjType, ObjEnum, ObjEnumVariant, ObjBigInt, ObjList, ObjNative, ObjRecord, ObjRecordInstance};
+use crate::native::{NativeMethod, native_methods, native_functions};
 
 const STACK_MAX: usize = 256;
 
@@ -22,20 +30,42 @@ pub struct VirtualMachine<'a> {
     stack_top: usize,
     strings: HashMap<ObjString, Value>,
     globals: HashMap<ObjString, Value>,
+    type_mode: TypeMode,
+    bigint: bool,
+    methods: HashMap<ObjType, HashMap<String, NativeMethod>>,
+    errors: Option<RefCell<Vec<String>>>,
     ip: usize
 }
 
 impl<'a> VirtualMachine<'a> {
 
     pub fn init_virtual_machine() -> Self {
-        VirtualMachine {
+        let mut vm = VirtualMachine {
             chunk: None,
             stack: Vec::with_capacity(STACK_MAX),
             stack_top: 0,
             strings: HashMap::new(),
             globals: HashMap::new(),
+            type_mode: TypeMode::TYPE_IGNORE,
+            bigint: true,
+            methods: native_methods(),
+            errors: None,
             ip: 0
+        };
+        for (name, function) in native_functions() {
+            let native = ObjNative::new(String::from(name), function);
+            vm.globals.insert(ObjString::from(String::from(name)), obj_val!(Box::from(native)));
         }
+        vm
+    }
+
+    pub fn set_type_mode(&mut self, type_mode: TypeMode) {
+        self.type_mode = type_mode
+    }
+
+    // integers that overflow a number are promoted to bigints unless disabled
+    pub fn set_bigint(&mut self, bigint: bool) {
+        self.bigint = bigint
     }
 
     pub fn push(&mut self, value: Value) {
@@ -45,8 +75,7 @@ impl<'a> VirtualMachine<'a> {
 
     pub fn pop(&mut self) -> Value {
         self.stack_top -= 1;
-        let self_stack = self.stack.clone();
-        self_stack[self.stack_top].clone()
+        self.stack.pop().unwrap()
     }
 
     pub fn peek(&mut self, dist: usize) -> &Value {
@@ -57,6 +86,8 @@ impl<'a> VirtualMachine<'a> {
         //let mut chunk = Chunk::init_chunk();
         let mut sc = Scanner::init_scanner(content);
         let mut compiler = CompilerParser::init_compiler(&mut sc, chunk);
+        compiler.set_type_mode(self.type_mode);
+        compiler.set_bigint(self.bigint);
         if !compiler.compile() {
             return InterpretResult::INTERPRET_COMPILE_ERROR;
         }
@@ -64,6 +95,50 @@ impl<'a> VirtualMachine<'a> {
         self.run()
     }
 
+    // runs source against the globals of this vm. a lone expression gives
+    // its value, statements give nil. compile and runtime errors come back
+    // as the error instead of being printed
+    pub fn eval(&mut self, source: &str) -> Result<Value, String> {
+        let mut chunk = Chunk::init_chunk();
+        if self.compile_source(source, true, &mut chunk).is_err() {
+            chunk = Chunk::init_chunk();
+            if let Err(errors) = self.compile_source(source, false, &mut chunk) {
+                return Err(errors.join("\n"));
+            }
+        }
+        let mut vm = VirtualMachine::init_virtual_machine();
+        vm.type_mode = self.type_mode;
+        vm.bigint = self.bigint;
+        vm.globals = std::mem::take(&mut self.globals);
+        vm.chunk = Some(&chunk);
+        vm.collect_errors();
+        let result = vm.run();
+        self.globals = std::mem::take(&mut vm.globals);
+        match result {
+            InterpretResult::INTERPRET_OK => Ok(vm.stack.pop().unwrap_or(nill!())),
+            _ => Err(vm.take_errors().pop().unwrap_or(String::from("Runtime error in eval")))
+        }
+    }
+
+    // keep runtime error messages for take_errors instead of printing them
+    pub fn collect_errors(&mut self) {
+        self.errors = Some(RefCell::new(Vec::new()));
+    }
+
+    pub fn take_errors(&mut self) -> Vec<String> {
+        self.errors.take().map(RefCell::into_inner).unwrap_or_default()
+    }
+
+    fn compile_source(&self, source: &str, expression: bool, chunk: &mut Chunk) -> Result<(), Vec<String>> {
+        let mut sc = Scanner::init_scanner(source);
+        let mut compiler = CompilerParser::init_compiler(&mut sc, chunk);
+        compiler.set_type_mode(self.type_mode);
+        compiler.set_bigint(self.bigint);
+        compiler.collect_errors();
+        let compiled = if expression { compiler.compile_expression() } else { compiler.compile() };
+        if compiled { Ok(()) } else { Err(compiler.take_errors()) }
+    }
+
     // pub fn interpret_(&mut self, content: &str) -> InterpretResult {
     //     InterpretResult::INTERPRET_OK
     // }
@@ -74,13 +149,9 @@ impl<'a> VirtualMachine<'a> {
 
     #[allow(mutable_borrow_reservation_conflict)]
     pub fn run(&mut self) -> InterpretResult {
-        for i in 0..self.stack_top {
-            print!("[");
-            ValueArray::print_value(&self.stack[i]);
-            print!("]")
+        if DEBUG_PRINT_CODE {
+            disassemble_chunk(&self.chunk.unwrap(), "code");
         }
-        println!();
-        disassemble_chunk(&self.chunk.unwrap(), "test chunk");
         let mut p = self.ip;
         let mut read_ip_increment = || { 
             let temp = p;
@@ -101,17 +172,20 @@ impl<'a> VirtualMachine<'a> {
                             .get(VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize),
                             None => Some(&nill!())
                         }.unwrap();
-                        ValueArray::print_value(constant);
                         self.push((*constant).clone());
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_NEGATE) => {
-                        if !is_number!(*self.peek(0)) {
+                        if !is_numeric!(*self.peek(0)) {
                             self.runtime_error("Operand must be a number");
                             return InterpretResult::INTERPRET_RUNTIME_ERROR;
                         }
-                        let op = -as_number!(self.pop());
-                        self.push(number_val!(op));
+                        let value = self.pop();
+                        if is_bigint!(value) {
+                            self.push(integer_val(-as_bigint!(value).get_value().clone()));
+                        } else {
+                            self.push(number_val!(-as_number!(value)));
+                        }
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_ADD) => {
@@ -119,10 +193,8 @@ impl<'a> VirtualMachine<'a> {
                             self.concatenate();
                             InterpretResult::INTERPRET_OK
                         }
-                        else if is_number!(*self.peek(0)) &&  is_number!(*self.peek(1)) {
-                            let b = self.pop();
-                            let a = self.pop();
-                            self.push(number_val!(as_number!(a) + as_number!(b)));
+                        else if is_numeric!(*self.peek(0)) &&  is_numeric!(*self.peek(1)) {
+                            self.arithmetic(Opcode::OP_ADD);
                             InterpretResult::INTERPRET_OK
                         }else {
                             self.runtime_error("Operands must be a number");
@@ -130,35 +202,27 @@ impl<'a> VirtualMachine<'a> {
                         }
                     },
                     Ok(Opcode::OP_MULTIPLY) => {
-                        if !is_number!(*self.peek(0)) ||  !is_number!(*self.peek(1)) {
+                        if !is_numeric!(*self.peek(0)) ||  !is_numeric!(*self.peek(1)) {
                             self.runtime_error("Operands must be a number");
                             return InterpretResult::INTERPRET_RUNTIME_ERROR;
                         }
-
-                        let b = self.pop();
-                        let a = self.pop();
-                        self.push(number_val!(as_number!(a) * as_number!(b)));
+                        self.arithmetic(Opcode::OP_MULTIPLY);
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_SUBTRACT) => {
-                        if !is_number!(*self.peek(0)) ||  !is_number!(*self.peek(1)) {
+                        if !is_numeric!(*self.peek(0)) ||  !is_numeric!(*self.peek(1)) {
                             self.runtime_error("Operands must be a number");
                             return InterpretResult::INTERPRET_RUNTIME_ERROR;
                         }
-
-                        let b = self.pop();
-                        let a = self.pop();
-                        self.push(number_val!(as_number!(a) - as_number!(b)));
+                        self.arithmetic(Opcode::OP_SUBTRACT);
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_DIVIDE) => {
-                        if !is_number!(*self.peek(0)) ||  !is_number!(*self.peek(1)) {
+                        if !is_numeric!(*self.peek(0)) ||  !is_numeric!(*self.peek(1)) {
                             self.runtime_error("Operands must be a number");
                             return InterpretResult::INTERPRET_RUNTIME_ERROR;
                         }
-                        let b = self.pop();
-                        let a = self.pop();
-                        self.push(number_val!(as_number!(a) / as_number!(b)));
+                        self.arithmetic(Opcode::OP_DIVIDE);
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_TRUE) => {
@@ -185,23 +249,21 @@ impl<'a> VirtualMachine<'a> {
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_GREATER) => {
-                        if !is_number!(*self.peek(0)) ||  !is_number!(*self.peek(1)) {
-                            self.runtime_error("Operands must be a number");
-                            return InterpretResult::INTERPRET_RUNTIME_ERROR;
-                        }
                         let b = self.pop();
                         let a = self.pop();
-                        self.push(boolean_val!(as_number!(a) > as_number!(b)));
+                        if !self.check_orderable(&a, &b) {
+                            return InterpretResult::INTERPRET_RUNTIME_ERROR;
+                        }
+                        self.push(boolean_val!(self.compare_values(&a, &b) == Some(Ordering::Greater)));
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_LESS) => {
-                        if !is_number!(*self.peek(0)) ||  !is_number!(*self.peek(1)) {
-                            self.runtime_error("Operands must be a number");
-                            return InterpretResult::INTERPRET_RUNTIME_ERROR;
-                        }
                         let b = self.pop();
                         let a = self.pop();
-                        self.push(boolean_val!(as_number!(a) < as_number!(b)));
+                        if !self.check_orderable(&a, &b) {
+                            return InterpretResult::INTERPRET_RUNTIME_ERROR;
+                        }
+                        self.push(boolean_val!(self.compare_values(&a, &b) == Some(Ordering::Less)));
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_PRINT) => {
@@ -260,15 +322,222 @@ impl<'a> VirtualMachine<'a> {
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_GET_LOCAL) => {
-                        let slot = read_ip_increment();
+                        let slot = VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize;
                         self.push(self.stack.get(slot).unwrap().clone());
                         InterpretResult::INTERPRET_OK
                     },
                     Ok(Opcode::OP_SET_LOCAL) => {
-                        let slot = read_ip_increment();
+                        let slot = VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize;
                         self.stack[slot] = self.peek(0).clone();
                         InterpretResult::INTERPRET_OK
                     },
+                    Ok(Opcode::OP_GET_PROPERTY) => {
+                        let constant: &Value = match self.chunk {
+                            Some(ch) => ch.get_constants().get_values()
+                            .get(VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize),
+                            None => Some(&nill!())
+                        }.unwrap();
+                        let name: ObjString = as_str!(*constant);
+                        if is_record_instance!(*self.peek(0)) {
+                            let record = as_record_instance!(self.pop());
+                            match record.get_field(name.get_string()) {
+                                Some(value) => self.push(value.clone()),
+                                None => {
+                                    self.runtime_error(&format!("Record {} has no field {}",
+                                        record.get_record_name(), name.get_string()));
+                                    return InterpretResult::INTERPRET_RUNTIME_ERROR;
+                                }
+                            }
+                        } else if is_enum!(*self.peek(0)) {
+                            let enum_obj: ObjEnum = as_enum!(self.pop());
+                            match self.make_variant(&enum_obj, name.get_string(), Vec::new()) {
+                                Some(variant) => self.push(obj_val!(Box::from(variant))),
+                                None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                            }
+                        } else {
+                            self.runtime_error("Only enums and records have properties");
+                            return InterpretResult::INTERPRET_RUNTIME_ERROR;
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_INVOKE) => {
+                        let constant: &Value = match self.chunk {
+                            Some(ch) => ch.get_constants().get_values()
+                            .get(VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize),
+                            None => Some(&nill!())
+                        }.unwrap();
+                        let name: ObjString = as_str!(*constant);
+                        let arg_count = VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize;
+                        let args = self.stack.split_off(self.stack_top - arg_count);
+                        self.stack_top -= arg_count;
+                        let receiver = self.pop();
+                        match self.invoke(&receiver, name.get_string(), args) {
+                            Some(result) => self.push(result),
+                            None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_INVOKE_LIST) => {
+                        let constant: &Value = match self.chunk {
+                            Some(ch) => ch.get_constants().get_values()
+                            .get(VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize),
+                            None => Some(&nill!())
+                        }.unwrap();
+                        let name: ObjString = as_str!(*constant);
+                        let args = self.pop();
+                        let receiver = self.pop();
+                        let args = as_list!(args).get_values().clone();
+                        match self.invoke(&receiver, name.get_string(), args) {
+                            Some(result) => self.push(result),
+                            None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_CHECK_TYPE) => {
+                        let constant: &Value = match self.chunk {
+                            Some(ch) => ch.get_constants().get_values()
+                            .get(VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize),
+                            None => Some(&nill!())
+                        }.unwrap();
+                        let expected: ObjString = as_str!(*constant);
+                        let actual = type_name(self.peek(0));
+                        if expected.get_string() != actual {
+                            self.runtime_error(&format!("Expected {} but got {}", expected.get_string(), actual));
+                            return InterpretResult::INTERPRET_RUNTIME_ERROR;
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_BUILD_LIST) => {
+                        let count = VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize;
+                        let values = self.stack.split_off(self.stack_top - count);
+                        self.stack_top -= count;
+                        self.push(obj_val!(Box::from(ObjList::from(values))));
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_SWAP) => {
+                        let b = self.pop();
+                        let a = self.pop();
+                        self.push(b);
+                        self.push(a);
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_CALL) => {
+                        let arg_count = VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize;
+                        let args = self.stack.split_off(self.stack_top - arg_count);
+                        self.stack_top -= arg_count;
+                        let callee = self.pop();
+                        match self.call_value(&callee, &args) {
+                            Some(result) => self.push(result),
+                            None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_CALL_LIST) => {
+                        let args = self.pop();
+                        let callee = self.pop();
+                        match self.call_value(&callee, as_list!(args).get_values()) {
+                            Some(result) => self.push(result),
+                            None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_IS) => {
+                        let constant: &Value = match self.chunk {
+                            Some(ch) => ch.get_constants().get_values()
+                            .get(VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize),
+                            None => Some(&nill!())
+                        }.unwrap();
+                        let name: ObjString = as_str!(*constant);
+                        let value = self.pop();
+                        let matches = name.get_string() == "Any" || type_name(&value) == name.get_string();
+                        self.push(boolean_val!(matches));
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_WITH) => {
+                        let count = VirtualMachine::inc_fn(self.chunk, &mut read_ip_increment) as usize;
+                        let pairs = self.stack.split_off(self.stack_top - 2 * count);
+                        self.stack_top -= 2 * count;
+                        let receiver = self.pop();
+                        if !is_record_instance!(receiver) {
+                            self.runtime_error(&format!("Undefined method with on {}", type_name(&receiver)));
+                            return InterpretResult::INTERPRET_RUNTIME_ERROR;
+                        }
+                        let mut record = as_record_instance!(receiver);
+                        for pair in pairs.chunks(2) {
+                            let field = as_str!(pair[0]);
+                            if !record.set_field(field.get_string(), pair[1].clone()) {
+                                self.runtime_error(&format!("Record {} has no field {}",
+                                    record.get_record_name(), field.get_string()));
+                                return InterpretResult::INTERPRET_RUNTIME_ERROR;
+                            }
+                        }
+                        self.push(obj_val!(Box::from(record)));
+                        InterpretResult::INTERPRET_OK
+                    },
+                    // lists are the only mutable values, anything else is
+                    // already frozen
+                    Ok(Opcode::OP_FREEZE) => {
+                        let value = self.pop();
+                        if is_list!(value) {
+                            let mut list = as_list!(value);
+                            list.freeze();
+                            self.push(obj_val!(Box::from(list)));
+                        } else {
+                            self.push(value);
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_LIST_APPEND) => {
+                        let value = self.pop();
+                        let list = self.pop();
+                        let mut values = as_list!(list).get_values().clone();
+                        values.push(value);
+                        self.push(obj_val!(Box::from(ObjList::from(values))));
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_LIST_EXTEND) => {
+                        let spread = self.pop();
+                        let list = self.pop();
+                        let mut values = as_list!(list).get_values().clone();
+                        match self.spread_values(&spread) {
+                            Some(spread) => values.extend(spread),
+                            None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                        }
+                        self.push(obj_val!(Box::from(ObjList::from(values))));
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_GET_INDEX) => {
+                        let index = self.pop();
+                        let target = self.pop();
+                        match self.get_index(&target, &index) {
+                            Some(value) => self.push(value),
+                            None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_SLICE) => {
+                        let step = self.pop();
+                        let end = self.pop();
+                        let start = self.pop();
+                        let target = self.pop();
+                        match self.slice(&target, &start, &end, &step) {
+                            Some(value) => self.push(value),
+                            None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
+                    Ok(Opcode::OP_SET_SLICE) => {
+                        let value = self.pop();
+                        let step = self.pop();
+                        let end = self.pop();
+                        let start = self.pop();
+                        let target = self.pop();
+                        match self.set_slice(&target, &start, &end, &step, &value) {
+                            Some(value) => self.push(value),
+                            None => return InterpretResult::INTERPRET_RUNTIME_ERROR
+                        }
+                        InterpretResult::INTERPRET_OK
+                    },
                     _ => return InterpretResult::INTERPRET_COMPILE_ERROR
                 };
             }
@@ -287,8 +556,279 @@ impl<'a> VirtualMachine<'a> {
         self.push(obj_val!(Box::from(obj_res)))
     }
 
+    fn get_index(&self, target: &Value, index: &Value) -> Option<Value> {
+        if !is_number!(*index) || as_number!(index).fract() != 0.0 {
+            self.runtime_error("Index must be an integer");
+            return None;
+        }
+        let index = as_number!(index) as i64;
+        let (len, value) = if is_str!(*target) {
+            let string = as_str!(*target);
+            let len = string.get_string().chars().count();
+            let position = if index < 0 { index + len as i64 } else { index };
+            let value = string.get_string().chars().nth(position.max(0) as usize)
+                .map(|ch| obj_val!(Box::from(ObjString::from(ch.to_string()))));
+            (len, if position < 0 { None } else { value })
+        } else if is_list!(*target) {
+            let list = as_list!(*target);
+            let len = list.get_values().len();
+            let position = if index < 0 { index + len as i64 } else { index };
+            (len, if position < 0 { None } else { list.get_values().get(position as usize).cloned() })
+        } else {
+            self.runtime_error("Only strings and lists can be indexed");
+            return None;
+        };
+        if value.is_none() {
+            self.runtime_error(&format!("Index {} out of range for length {}", index, len));
+        }
+        value
+    }
+
+    fn slice(&self, target: &Value, start: &Value, end: &Value, step: &Value) -> Option<Value> {
+        if is_str!(*target) {
+            let chars: Vec<char> = as_str_raw!(*target).chars().collect();
+            let indices = self.slice_indices(chars.len(), start, end, step)?;
+            let res: String = indices.iter().map(|index| chars[*index]).collect();
+            Some(obj_val!(Box::from(ObjString::from(res))))
+        } else if is_list!(*target) {
+            let list = as_list!(*target);
+            let indices = self.slice_indices(list.get_values().len(), start, end, step)?;
+            let res: Vec<Value> = indices.iter().map(|index| list.get_values()[*index].clone()).collect();
+            Some(obj_val!(Box::from(ObjList::from(res))))
+        } else {
+            self.runtime_error("Only strings and lists can be sliced");
+            None
+        }
+    }
+
+    // lists are values, so assigning to a slice builds the updated list
+    // which the compiler stores back into the sliced variable
+    fn set_slice(&self, target: &Value, start: &Value, end: &Value, step: &Value, value: &Value) -> Option<Value> {
+        if !is_list!(*target) {
+            self.runtime_error("Only slices of lists can be assigned");
+            return None;
+        }
+        if !is_list!(*value) {
+            self.runtime_error("Can only assign a list to a slice");
+            return None;
+        }
+        if as_list!(*target).is_frozen() {
+            self.runtime_error("Can't modify a frozen List");
+            return None;
+        }
+        let mut values = as_list!(*target).get_values().clone();
+        let replacement = as_list!(*value).get_values().clone();
+        let indices = self.slice_indices(values.len(), start, end, step)?;
+        if is_nill!(*step) || as_number!(step) == 1.0 {
+            let len = values.len() as i64;
+            let from = match self.slice_bound(start, len)? {
+                Some(index) => index.max(0).min(len),
+                None => 0
+            };
+            let to = match self.slice_bound(end, len)? {
+                Some(index) => index.max(from).min(len),
+                None => len
+            };
+            values.splice(from as usize..to as usize, replacement);
+        } else {
+            if indices.len() != replacement.len() {
+                self.runtime_error(&format!("Cannot assign {} values to a slice of {} elements",
+                    replacement.len(), indices.len()));
+                return None;
+            }
+            for (index, value) in indices.iter().zip(replacement) {
+                values[*index] = value;
+            }
+        }
+        Some(obj_val!(Box::from(ObjList::from(values))))
+    }
+
+    // a slice bound as an index, negative bounds count from the end
+    fn slice_bound(&self, bound: &Value, len: i64) -> Option<Option<i64>> {
+        if is_nill!(*bound) {
+            return Some(None);
+        }
+        if !is_number!(*bound) || as_number!(bound).fract() != 0.0 {
+            self.runtime_error("Slice bounds must be integers or nil");
+            return None;
+        }
+        let index = as_number!(bound) as i64;
+        Some(Some(if index < 0 { index + len } else { index }))
+    }
+
+    // positions selected by a slice, following the usual start:end:step rules
+    fn slice_indices(&self, len: usize, start: &Value, end: &Value, step: &Value) -> Option<Vec<usize>> {
+        let len = len as i64;
+        let step = match self.slice_bound(step, 0)? {
+            Some(0) => {
+                self.runtime_error("Slice step cannot be zero");
+                return None;
+            },
+            Some(_) => as_number!(step) as i64,
+            None => 1
+        };
+        let start = self.slice_bound(start, len)?;
+        let end = self.slice_bound(end, len)?;
+        let mut indices = Vec::new();
+        if step > 0 {
+            let mut index = start.unwrap_or(0).max(0).min(len);
+            let end = end.unwrap_or(len).max(0).min(len);
+            while index < end {
+                indices.push(index as usize);
+                index += step;
+            }
+        } else {
+            let mut index = start.unwrap_or(len - 1).max(-1).min(len - 1);
+            let end = end.unwrap_or(-1).max(-1).min(len - 1);
+            while index > end {
+                indices.push(index as usize);
+                index += step;
+            }
+        }
+        Some(indices)
+    }
+
+    // calling a record builds an instance of it, natives run their rust function
+    fn call_value(&mut self, callee: &Value, args: &[Value]) -> Option<Value> {
+        if is_record!(*callee) {
+            return self.make_record(&as_record!(*callee), args).map(|record| obj_val!(Box::from(record)));
+        }
+        if !is_native!(*callee) {
+            self.runtime_error(&format!("Can only call functions and records, not {}", type_name(callee)));
+            return None;
+        }
+        match (as_native!(*callee).get_function())(self, args) {
+            Ok(result) => Some(result),
+            Err(message) => {
+                self.runtime_error(&message);
+                None
+            }
+        }
+    }
+
+    // a call on an enum builds one of its variants, anything else goes to
+    // the native methods of the receiver
+    fn invoke(&self, receiver: &Value, name: &str, args: Vec<Value>) -> Option<Value> {
+        if is_enum!(*receiver) {
+            let enum_obj: ObjEnum = as_enum!(*receiver);
+            return self.make_variant(&enum_obj, name, args).map(|variant| obj_val!(Box::from(variant)));
+        }
+        self.invoke_native(receiver, name, &args)
+    }
+
+    // values produced by '...', a list spreads its elements and a string its characters
+    fn spread_values(&self, value: &Value) -> Option<Vec<Value>> {
+        if is_list!(*value) {
+            return Some(as_list!(*value).get_values().clone());
+        }
+        if is_str!(*value) {
+            return Some(as_str_raw!(*value).chars()
+                .map(|ch| obj_val!(Box::from(ObjString::from(ch.to_string())))).collect());
+        }
+        self.runtime_error(&format!("Can't spread a value of type {}", type_name(value)));
+        None
+    }
+
+    // call a method from the native table of the receiver's object type
+    fn invoke_native(&self, receiver: &Value, name: &str, args: &[Value]) -> Option<Value> {
+        let mut method = None;
+        if is_obj!(*receiver) {
+            let obj_type = *as_obj!(*receiver).get_type();
+            method = self.methods.get(&obj_type).and_then(|table| table.get(name));
+        }
+        let method = match method {
+            Some(method) => method,
+            None => {
+                self.runtime_error(&format!("Undefined method {} on {}", name, type_name(receiver)));
+                return None;
+            }
+        };
+        match method(receiver, args) {
+            Ok(result) => Some(result),
+            Err(message) => {
+                self.runtime_error(&message);
+                None
+            }
+        }
+    }
+
+    fn make_variant(&self, enum_obj: &ObjEnum, name: &str, values: Vec<Value>) -> Option<ObjEnumVariant> {
+        let variant = match enum_obj.get_variant(name) {
+            Some(variant) => variant,
+            None => {
+                self.runtime_error(&format!("Enum {} has no variant {}", enum_obj.get_name(), name));
+                return None;
+            }
+        };
+        if variant.get_arity() != values.len() {
+            self.runtime_error(&format!("Variant {}.{} expects {} values but got {}",
+                enum_obj.get_name(), name, variant.get_arity(), values.len()));
+            return None;
+        }
+        Some(ObjEnumVariant::new(enum_obj, String::from(name), values))
+    }
+
+    fn make_record(&self, record: &ObjRecord, values: &[Value]) -> Option<ObjRecordInstance> {
+        if record.get_fields().len() != values.len() {
+            self.runtime_error(&format!("Record {} expects {} values but got {}",
+                record.get_name(), record.get_fields().len(), values.len()));
+            return None;
+        }
+        Some(ObjRecordInstance::new(record, values.to_vec()))
+    }
+
+    // numbers stay numbers unless both operands are integers and the result
+    // can't be held exactly, then the operation is redone on bigints
+    fn arithmetic(&mut self, op: Opcode) {
+        let b = self.pop();
+        let a = self.pop();
+        if is_number!(a) && is_number!(b) {
+            let result = float_arithmetic(&op, as_number!(a), as_number!(b));
+            if !self.bigint || result.abs() <= MAX_SAFE_INTEGER || result.is_nan() {
+                self.push(number_val!(result));
+                return;
+            }
+        }
+        match (as_integer(&a), as_integer(&b)) {
+            (Some(x), Some(y)) if self.bigint => self.push(integer_arithmetic(&op, x, y)),
+            _ => self.push(number_val!(float_arithmetic(&op, as_float(&a), as_float(&b))))
+        }
+    }
+
+    fn compare_numbers(&self, a: &Value, b: &Value) -> Option<Ordering> {
+        if is_number!(*a) && is_number!(*b) {
+            return as_number!(a).partial_cmp(&as_number!(b));
+        }
+        match (as_integer(a), as_integer(b)) {
+            (Some(x), Some(y)) => Some(x.cmp(&y)),
+            _ => as_float(a).partial_cmp(&as_float(b))
+        }
+    }
+
+    // only numbers and strings have an order, strings compare by code point
+    fn check_orderable(&self, a: &Value, b: &Value) -> bool {
+        if (is_numeric!(*a) && is_numeric!(*b)) || (is_str!(*a) && is_str!(*b)) {
+            return true;
+        }
+        self.runtime_error(&format!("Can't compare {} with {}, operands must be two numbers or two strings",
+            type_name(a), type_name(b)));
+        false
+    }
+
+    fn compare_values(&self, a: &Value, b: &Value) -> Option<Ordering> {
+        if is_str!(*a) && is_str!(*b) {
+            return Some(as_str_raw!(*a).cmp(as_str_raw!(*b)));
+        }
+        self.compare_numbers(a, b)
+    }
+
+    // numbers compare by value whatever their representation, lists,
+    // variants and records compare element by element
     fn values_equal(&self, a: Value, b: Value) -> bool {
-        if *a.get_type_ref() != *a.get_type_ref(){
+        if (is_bigint!(a) || is_bigint!(b)) && is_numeric!(a) && is_numeric!(b) {
+            return self.compare_numbers(&a, &b) == Some(Ordering::Equal);
+        }
+        if *a.get_type_ref() != *b.get_type_ref(){
             return false;
         }
         match *a.get_type_ref() {
@@ -296,15 +836,76 @@ impl<'a> VirtualMachine<'a> {
             ValueType::VAL_NIL => true,
             ValueType::VAL_NUMBER => as_number!(a) == as_number!(b),
             ValueType::VAL_OBJ => {
-                let a = String::from(as_str_raw!(a));
-                let b = String::from(as_str_raw!(b));
-                a == b
+                if *as_obj!(a).get_type() != *as_obj!(b).get_type() {
+                    return false;
+                }
+                match *as_obj!(a).get_type() {
+                    ObjType::OBJ_STRING => {
+                        let a = String::from(as_str_raw!(a));
+                        let b = String::from(as_str_raw!(b));
+                        a == b
+                    },
+                    ObjType::OBJ_ENUM => as_enum!(a).get_id() == as_enum!(b).get_id(),
+                    ObjType::OBJ_ENUM_VARIANT => {
+                        let a = as_enum_variant!(a);
+                        let b = as_enum_variant!(b);
+                        a.get_enum_id() == b.get_enum_id() && a.get_name() == b.get_name()
+                        && a.get_values().len() == b.get_values().len()
+                        && a.get_values().iter().zip(b.get_values().iter())
+                            .all(|(x, y)| self.values_equal(x.clone(), y.clone()))
+                    },
+                    ObjType::OBJ_BIGINT => as_bigint!(a).get_value() == as_bigint!(b).get_value(),
+                    ObjType::OBJ_LIST => {
+                        let a = as_list!(a);
+                        let b = as_list!(b);
+                        a.get_values().len() == b.get_values().len()
+                        && a.get_values().iter().zip(b.get_values().iter())
+                            .all(|(x, y)| self.values_equal(x.clone(), y.clone()))
+                    },
+                    ObjType::OBJ_NATIVE => as_native!(a).get_name() == as_native!(b).get_name(),
+                    ObjType::OBJ_RECORD => as_record!(a) == as_record!(b),
+                    ObjType::OBJ_RECORD_INSTANCE => {
+                        let a = as_record_instance!(a);
+                        let b = as_record_instance!(b);
+                        a.get_record_name() == b.get_record_name() && a.get_fields() == b.get_fields()
+                        && a.get_values().iter().zip(b.get_values().iter())
+                            .all(|(x, y)| self.values_equal(x.clone(), y.clone()))
+                    }
+                }
             }
             
         }
     }
 
     fn runtime_error(&self, message: &str ) {
-        println!("{}", message);
+        match &self.errors {
+            Some(errors) => errors.borrow_mut().push(String::from(message)),
+            None => println!("{}", message)
+        }
+    }
+}
+
+fn float_arithmetic(op: &Opcode, a: f64, b: f64) -> f64 {
+    match op {
+        Opcode::OP_ADD => a + b,
+        Opcode::OP_SUBTRACT => a - b,
+        Opcode::OP_MULTIPLY => a * b,
+        _ => a / b
+    }
+}
+
+// division only stays an integer when it is exact
+fn integer_arithmetic(op: &Opcode, a: BigInt, b: BigInt) -> Value {
+    match op {
+        Opcode::OP_ADD => integer_val(a + b),
+        Opcode::OP_SUBTRACT => integer_val(a - b),
+        Opcode::OP_MULTIPLY => integer_val(a * b),
+        _ => {
+            if !b.is_zero() && (&a % &b).is_zero() {
+                integer_val(a / b)
+            } else {
+                number_val!(float_arithmetic(op, a.to_f64().unwrap_or(f64::NAN), b.to_f64().unwrap_or(f64::NAN)))
+            }
+        }
     }
 }